@@ -0,0 +1,143 @@
+//! Decryption benchmarks without a database
+//!
+//! The `_decrypt` query benches time fetching and decrypting together. This
+//! bench decrypts ciphertexts that were encrypted up front, so the cost of
+//! `decrypt_eql` can be separated from the query. Payloads have the same shape
+//! and column config as the ingested tables:
+//! - int: integers with an ORE index, as in `integer_encrypted`
+//! - string: names with unique and match indexes, as in `string_encrypted`
+//! - json_small, json_large: JSON documents with a SteVec index, as in
+//!   `json_small_encrypted` and `json_large_encrypted`
+//!
+//! Benchmark IDs are `DECRYPT/{payload}/{batch}` and throughput is reported in
+//! records.
+//!
+//! Environment variables:
+//! - BATCH_SIZES: records per `decrypt_eql` call (default: 1,10,100,1000)
+//! - CS_CIPHER_BACKEND: `zerokms` (default) or `local` to run offline with fixed test keys
+
+use cipherstash_client::{
+    encryption::Plaintext,
+    eql::{decrypt_eql, encrypt_eql, EqlCiphertext, EqlOperation, Identifier, PreparedPlaintext},
+    schema::{ColumnConfig, ColumnType},
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use dbbenches::{
+    env_list_or, init_scoped_cipher, FakeJsonLarge, FakeJsonSmall, IndexKind, WrappedJson,
+};
+use fake::{
+    faker::name::raw::Name,
+    locales::EN,
+    rand::{rngs::StdRng, SeedableRng},
+    Fake, Faker,
+};
+use std::borrow::Cow;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+const DEFAULT_BATCH_SIZES: &[usize] = &[1, 10, 100, 1000];
+
+/// A payload type, its column config and a generator for its values.
+struct Payload {
+    name: &'static str,
+    cast: ColumnType,
+    indexes: &'static [IndexKind],
+    generate: fn(&mut StdRng) -> Plaintext,
+}
+
+impl Payload {
+    fn column_config(&self) -> ColumnConfig {
+        self.indexes.iter().fold(
+            ColumnConfig::build("value").casts_as(self.cast),
+            |config, index| config.add_index(index.index()),
+        )
+    }
+}
+
+const PAYLOADS: &[Payload] = &[
+    Payload {
+        name: "int",
+        cast: ColumnType::Int,
+        indexes: &[IndexKind::Ore],
+        generate: |rng| Faker.fake_with_rng::<i32, _>(rng).into(),
+    },
+    Payload {
+        name: "string",
+        cast: ColumnType::Utf8Str,
+        indexes: &[IndexKind::Unique, IndexKind::Match],
+        generate: |rng| Name(EN).fake_with_rng::<String, _>(rng).into(),
+    },
+    Payload {
+        name: "json_small",
+        cast: ColumnType::JsonB,
+        indexes: &[IndexKind::SteVec],
+        generate: |rng| FakeJsonSmall.fake_with_rng::<WrappedJson, _>(rng).into(),
+    },
+    Payload {
+        name: "json_large",
+        cast: ColumnType::JsonB,
+        indexes: &[IndexKind::SteVec],
+        generate: |rng| FakeJsonLarge.fake_with_rng::<WrappedJson, _>(rng).into(),
+    },
+];
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let cipher = rt
+        .block_on(init_scoped_cipher())
+        .expect("Failed to initialize cipher");
+    let batch_sizes =
+        env_list_or("BATCH_SIZES", DEFAULT_BATCH_SIZES).expect("Failed to parse BATCH_SIZES");
+    let max_batch = batch_sizes.iter().copied().max().unwrap_or(0);
+
+    let mut group = c.benchmark_group("DECRYPT");
+    group.sample_size(10);
+
+    for payload in PAYLOADS {
+        // Encrypt the largest batch once, smaller batches decrypt a prefix of it
+        let column_config = payload.column_config();
+        let identifier = Identifier::new(format!("{}_encrypted", payload.name), "value");
+        let mut rng = StdRng::seed_from_u64(0);
+        let prepared = (0..max_batch)
+            .map(|_| {
+                PreparedPlaintext::new(
+                    Cow::Borrowed(&column_config),
+                    identifier.clone(),
+                    (payload.generate)(&mut rng),
+                    EqlOperation::Store,
+                )
+            })
+            .collect::<Vec<_>>();
+        let ciphertexts: Vec<EqlCiphertext> = rt
+            .block_on(encrypt_eql(
+                Arc::clone(&cipher),
+                prepared,
+                &Default::default(),
+            ))
+            .expect("Failed to encrypt payloads");
+
+        for &batch_size in &batch_sizes {
+            let batch = &ciphertexts[..batch_size];
+
+            group.throughput(Throughput::Elements(batch_size as u64));
+            group.bench_function(format!("{}/{batch_size}", payload.name), |b| {
+                b.to_async(&rt).iter_batched(
+                    || batch.to_vec(),
+                    |batch| async {
+                        black_box(
+                            decrypt_eql(Arc::clone(&cipher), batch, &Default::default())
+                                .await
+                                .unwrap(),
+                        );
+                    },
+                    BatchSize::SmallInput,
+                )
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,205 @@
+//! Encryption benchmarks without a database
+//!
+//! Times `encrypt_eql` on its own for each index type, so the cost of the
+//! encryption tier can be planned separately from Postgres. Each column config
+//! is encrypted with `EqlOperation::Store` (a full ciphertext with every index
+//! term, as written on ingest) and `EqlOperation::Query` (a query term for one
+//! index, as bound to a query), across batch and payload sizes:
+//! - ore: integers with an ORE index
+//! - match: strings with a match (bloom filter) index, by length in characters
+//! - unique: strings with a unique (HMAC) index, by length in characters
+//! - ste_vec: JSON objects with a SteVec index, by number of fields
+//!
+//! Benchmark IDs are `ENCRYPT_{STORE,QUERY}/{config}/{payload}/{batch}` and
+//! throughput is reported in records.
+//!
+//! Environment variables:
+//! - BATCH_SIZES: records per `encrypt_eql` call (default: 1,10,100,1000)
+//! - STRING_LENGTHS: lengths of the match and unique payloads (default: 16,256,4096)
+//! - JSON_FIELDS: fields in the SteVec payloads (default: 4,16,64)
+//! - CS_CIPHER_BACKEND: `zerokms` (default) or `local` to run offline with fixed test keys
+
+use cipherstash_client::{
+    encryption::{Plaintext, QueryOp},
+    eql::{encrypt_eql, EqlOperation, Identifier, PreparedPlaintext},
+    schema::{column::IndexType, ColumnConfig, ColumnType},
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use dbbenches::{env_list_or, init_scoped_cipher, IndexKind, WrappedJson};
+use fake::rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+const DEFAULT_BATCH_SIZES: &[usize] = &[1, 10, 100, 1000];
+const DEFAULT_STRING_LENGTHS: &[usize] = &[16, 256, 4096];
+const DEFAULT_JSON_FIELDS: &[usize] = &[4, 16, 64];
+
+/// A column config and a way to generate its payloads.
+struct Config {
+    name: &'static str,
+    index: IndexKind,
+    cast: ColumnType,
+    /// Payload label and size, passed to `generate`.
+    payloads: Vec<(String, usize)>,
+    generate: fn(&mut StdRng, usize) -> Plaintext,
+}
+
+impl Config {
+    fn column_config(&self) -> ColumnConfig {
+        ColumnConfig::build("value")
+            .casts_as(self.cast)
+            .add_index(self.index.index())
+    }
+
+    fn index_type(&self) -> IndexType {
+        self.index.index().index_type
+    }
+}
+
+fn int(rng: &mut StdRng, _: usize) -> Plaintext {
+    rng.random::<i32>().into()
+}
+
+/// Lowercase words of up to 8 letters, `len` characters in all.
+fn string(rng: &mut StdRng, len: usize) -> Plaintext {
+    let value: String = (0..len)
+        .map(|i| {
+            if i % 9 == 8 {
+                ' '
+            } else {
+                rng.random_range(b'a'..=b'z') as char
+            }
+        })
+        .collect();
+    value.into()
+}
+
+/// A flat object of `fields` short string and integer fields.
+fn json(rng: &mut StdRng, fields: usize) -> Plaintext {
+    let object: Map<String, Value> = (0..fields)
+        .map(|i| {
+            let value = if i % 2 == 0 {
+                Value::from(rng.random_range(0..100_000))
+            } else {
+                Value::from(format!("value-{}", rng.random::<u32>()))
+            };
+            (format!("field_{i}"), value)
+        })
+        .collect();
+    WrappedJson(Value::Object(object)).into()
+}
+
+fn configs() -> Vec<Config> {
+    let strings: Vec<_> = env_list_or("STRING_LENGTHS", DEFAULT_STRING_LENGTHS)
+        .expect("Failed to parse STRING_LENGTHS")
+        .into_iter()
+        .map(|len| (format!("string_{len}"), len))
+        .collect();
+    let json_fields: Vec<_> = env_list_or("JSON_FIELDS", DEFAULT_JSON_FIELDS)
+        .expect("Failed to parse JSON_FIELDS")
+        .into_iter()
+        .map(|fields| (format!("json_{fields}_fields"), fields))
+        .collect();
+
+    vec![
+        Config {
+            name: "ore",
+            index: IndexKind::Ore,
+            cast: ColumnType::Int,
+            payloads: vec![("int".to_string(), 0)],
+            generate: int,
+        },
+        Config {
+            name: "match",
+            index: IndexKind::Match,
+            cast: ColumnType::Utf8Str,
+            payloads: strings.clone(),
+            generate: string,
+        },
+        Config {
+            name: "unique",
+            index: IndexKind::Unique,
+            cast: ColumnType::Utf8Str,
+            payloads: strings,
+            generate: string,
+        },
+        Config {
+            name: "ste_vec",
+            index: IndexKind::SteVec,
+            cast: ColumnType::JsonB,
+            payloads: json_fields,
+            generate: json,
+        },
+    ]
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let cipher = rt
+        .block_on(init_scoped_cipher())
+        .expect("Failed to initialize cipher");
+    let batch_sizes =
+        env_list_or("BATCH_SIZES", DEFAULT_BATCH_SIZES).expect("Failed to parse BATCH_SIZES");
+    let identifier = Identifier::new("encrypt_bench", "value");
+
+    for (group_name, store) in [("ENCRYPT_STORE", true), ("ENCRYPT_QUERY", false)] {
+        let mut group = c.benchmark_group(group_name);
+        group.sample_size(10);
+
+        for config in configs() {
+            let column_config = config.column_config();
+            let index_type = config.index_type();
+            // EqlOperation isn't Clone
+            let operation = || {
+                if store {
+                    EqlOperation::Store
+                } else {
+                    EqlOperation::Query(&index_type, QueryOp::Default)
+                }
+            };
+
+            for (payload, size) in &config.payloads {
+                let mut rng = StdRng::seed_from_u64(0);
+                for &batch_size in &batch_sizes {
+                    let plaintexts: Vec<Plaintext> = (0..batch_size)
+                        .map(|_| (config.generate)(&mut rng, *size))
+                        .collect();
+
+                    group.throughput(Throughput::Elements(batch_size as u64));
+                    group.bench_function(format!("{}/{payload}/{batch_size}", config.name), |b| {
+                        b.to_async(&rt).iter_batched(
+                            || {
+                                plaintexts
+                                    .iter()
+                                    .map(|plaintext| {
+                                        PreparedPlaintext::new(
+                                            Cow::Borrowed(&column_config),
+                                            identifier.clone(),
+                                            plaintext.clone(),
+                                            operation(),
+                                        )
+                                    })
+                                    .collect::<Vec<_>>()
+                            },
+                            |prepared| async {
+                                black_box(
+                                    encrypt_eql(Arc::clone(&cipher), prepared, &Default::default())
+                                        .await
+                                        .unwrap(),
+                                );
+                            },
+                            BatchSize::SmallInput,
+                        )
+                    });
+                }
+            }
+        }
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -1,127 +1,123 @@
-use cipherstash_client::{
-    credentials::ServiceCredentials,
-    encryption::ScopedCipher,
-    eql::Identifier,
-    schema::{
-        column::{Index, IndexType},
-        ColumnConfig, ColumnType,
-    },
+//! ore-rs primitive benchmarks
+//!
+//! Times the ORE scheme underneath the EQL `ore` index on its own, with no
+//! EQL encoding, ZeroKMS or Postgres involved:
+//! - encrypt_left: a left (query) ciphertext
+//! - encrypt: a full (left and right) ciphertext, as stored
+//! - compare: comparing two ciphertexts
+//! - compare_bytes: comparing two serialized ciphertexts, as Postgres does
+//! - sort: sorting a vector of ciphertexts
+//!
+//! Encryption and comparison are timed for u8, u16, u32 and u64 inputs, which
+//! have 1, 2, 4 and 8 blocks. Benchmark IDs are
+//! `ORE_RS/{operation}/{type}/{n}`, where `n` is the number of values per
+//! iteration. The group isn't `ORE`, which the ORE query scenarios use.
+//!
+//! Environment variables:
+//! - SORT_SIZES: ciphertexts per sorted vector (default: 100,1000,10000)
+
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BatchSize, BenchmarkGroup,
+    Criterion, Throughput,
 };
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use dbbenches::{init_scoped_cipher, EncryptedQuery, EncryptedQueryBuilder};
-use sqlx::postgres::PgPoolOptions;
-use std::sync::Arc;
-use tokio::runtime::Runtime;
-
-static QUERY_TEMPLATES: &[(&str, i32, &str)] = &[
-    (
-        "SELECT value FROM {TABLE} WHERE value = $1 LIMIT 1",
-        5000,
-        "exact",
-    ),
-    (
-        "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1 LIMIT 10",
-        5000,
-        "range_gt_10",
-    ),
-    (
-        "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1 LIMIT 100",
-        5000,
-        "range_gt_100",
-    ),
-    (
-        "SELECT id,value::jsonb FROM {TABLE} WHERE value < $1 LIMIT 10",
-        5000,
-        "range_lt_10",
-    ),
-    (
-        "SELECT id,value::jsonb FROM {TABLE} WHERE value < $1 LIMIT 100",
-        5000,
-        "range_lt_100",
-    ),
-    (
-        "SELECT id,value::jsonb FROM {TABLE} WHERE value < $1 ORDER BY value LIMIT 10",
-        5000,
-        "range_lt_ordered_10",
-    ),
-];
+use dbbenches::env_list_or;
+use fake::rand::{rngs::StdRng, Rng, SeedableRng};
+use ore_rs::{scheme::bit2::OREAES128, CipherText, ORECipher, OREEncrypt, PlainText};
+
+const DEFAULT_SORT_SIZES: &[usize] = &[100, 1000, 10000];
+
+/// Benchmarks for `N`-byte inputs, generated by `input`.
+fn bench_width<const N: usize>(
+    group: &mut BenchmarkGroup<WallTime>,
+    ore: &OREAES128,
+    name: &str,
+    input: fn(&mut StdRng) -> PlainText<N>,
+) {
+    let mut rng = StdRng::seed_from_u64(0);
+    group.throughput(Throughput::Elements(1));
+
+    group.bench_function(format!("encrypt_left/{name}/1"), |b| {
+        b.iter_batched(
+            || input(&mut rng),
+            |plaintext| black_box(plaintext.encrypt_left(ore).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
 
-async fn build_query(
-    cipher: Arc<ScopedCipher<ServiceCredentials>>,
-    query: &str,
-    x: i32,
-    table_name: &str,
-) -> EncryptedQuery {
-    let column_config = ColumnConfig::build("value")
-        .casts_as(ColumnType::Int)
-        .add_index(Index::new_ore());
+    group.bench_function(format!("encrypt/{name}/1"), |b| {
+        b.iter_batched(
+            || input(&mut rng),
+            |plaintext| black_box(plaintext.encrypt(ore).unwrap()),
+            BatchSize::SmallInput,
+        )
+    });
 
-    let identifier = Identifier::new(table_name, "value");
+    let pairs: Vec<(CipherText<OREAES128, N>, CipherText<OREAES128, N>)> = (0..1000)
+        .map(|_| {
+            (
+                input(&mut rng).encrypt(ore).unwrap(),
+                input(&mut rng).encrypt(ore).unwrap(),
+            )
+        })
+        .collect();
+    let mut i = 0;
+    group.bench_function(format!("compare/{name}/1"), |b| {
+        b.iter(|| {
+            let (a, b) = &pairs[i % pairs.len()];
+            i += 1;
+            black_box(a.cmp(b))
+        })
+    });
 
-    EncryptedQueryBuilder::new(column_config, identifier)
-        .index_type(IndexType::Ore)
-        .statement(query)
-        .build_query(x, cipher)
-        .await
-        .expect("Failed to build encrypted query")
+    let pairs: Vec<(Vec<u8>, Vec<u8>)> = pairs
+        .iter()
+        .map(|(a, b)| (a.to_bytes(), b.to_bytes()))
+        .collect();
+    let mut i = 0;
+    group.bench_function(format!("compare_bytes/{name}/1"), |b| {
+        b.iter(|| {
+            let (a, b) = &pairs[i % pairs.len()];
+            i += 1;
+            black_box(OREAES128::compare_raw_slices(a, b))
+        })
+    });
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
-    let rt = Runtime::new().unwrap();
-
-    let target_rows = std::env::var("TARGET_ROWS")
-        .unwrap_or_else(|_| "unknown".to_string());
-
-    // Determine table suffix based on TARGET_ROWS
-    let table_suffix = match target_rows.as_str() {
-        "10000" | "100000" | "1000000" | "10000000" => format!("_{}", target_rows),
-        _ => String::new(), // fallback to base table for unknown values
-    };
-    let table_name = format!("integer_encrypted{}", table_suffix);
-
-    let (pool, cipher) = rt.block_on(async {
-        let database_url =
-            std::env::var("DATABASE_URL").expect("DATABASE_URL environment variable must be set");
+    let mut rng = StdRng::seed_from_u64(0);
+    let ore = OREAES128::init(&rng.random(), &rng.random()).expect("Failed to initialize ORE");
 
-        let pool = PgPoolOptions::new()
-            .max_connections(5)
-            .connect(&database_url)
-            .await
-            .expect("Failed to connect to database");
+    let mut group = c.benchmark_group("ORE_RS");
 
-        let cipher = init_scoped_cipher()
-            .await
-            .expect("Failed to initialize ScopedCipher");
-
-        (pool, cipher)
+    bench_width(&mut group, &ore, "u8", |rng| {
+        rng.random::<u8>().to_be_bytes()
     });
-
-    let queries = rt.block_on(async {
-        let mut queries = Vec::with_capacity(QUERY_TEMPLATES.len());
-        for (query_template, x, _) in QUERY_TEMPLATES {
-            let query_str = query_template.replace("{TABLE}", &table_name);
-            let query = build_query(Arc::clone(&cipher), &query_str, *x, &table_name).await;
-            queries.push(query);
-        }
-        queries
+    bench_width(&mut group, &ore, "u16", |rng| {
+        rng.random::<u16>().to_be_bytes()
+    });
+    bench_width(&mut group, &ore, "u32", |rng| {
+        rng.random::<u32>().to_be_bytes()
+    });
+    bench_width(&mut group, &ore, "u64", |rng| {
+        rng.random::<u64>().to_be_bytes()
     });
 
-    let mut group = c.benchmark_group("ORE");
-    group.sample_size(10);
-
-    for (i, query) in queries.into_iter().enumerate() {
-        let (_, _, scenario) = QUERY_TEMPLATES[i];
-        
-        group.bench_function(format!("ore/{}/{}", scenario, target_rows), |b| {
-            b.to_async(&rt).iter(|| async {
-                let _: Vec<_> = query.execute(&pool).await.unwrap();
-            })
-        });
-
-        group.bench_function(format!("ore_decrypt/{}/{}", scenario, target_rows), |b| {
-            b.to_async(&rt).iter(|| async {
-                let _r: Vec<i32> = black_box(query.execute_and_decrypt(&pool).await.unwrap());
-            })
+    for size in env_list_or("SORT_SIZES", DEFAULT_SORT_SIZES).expect("Failed to parse SORT_SIZES") {
+        let ciphertexts: Vec<CipherText<OREAES128, 8>> = (0..size)
+            .map(|_| rng.random::<u64>().encrypt(&ore).unwrap())
+            .collect();
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_function(format!("sort/u64/{size}"), |b| {
+            // Ciphertexts aren't Clone, so each iteration sorts references to them
+            b.iter_batched(
+                || ciphertexts.iter().collect::<Vec<_>>(),
+                |mut ciphertexts| {
+                    ciphertexts.sort();
+                    black_box(ciphertexts)
+                },
+                BatchSize::LargeInput,
+            )
         });
     }
 
@@ -0,0 +1,313 @@
+//! Query benchmarks driven by scenario files
+//!
+//! Runs every scenario in the files named by `SCENARIO` (comma-separated paths),
+//! or in every file under `scenarios/` when it isn't set. See
+//! `dbbenches::ScenarioFile` for the file format.
+//!
+//! Environment variables:
+//! - DATABASE_URL: PostgreSQL connection string (required)
+//! - TARGET_ROWS: row count of the tables to query (10000, 100000, 1000000 or 10000000)
+//! - SCENARIO: scenario files to run (default: all of `scenarios/*.toml`)
+//! - VERIFY: check results against the plaintext tables instead of timing them
+//! - DECRYPT_CHUNK_SIZE: most rows decrypted at once when streaming (default: 100)
+//! - PARAM_POOL_SIZE: queries per scenario with a parameter distribution (default: the scenario's `pool`, or 32)
+//! - POOL_SEED: seed for drawing parameters (default: 0)
+//! - POOL_PROFILE_RUNS: times each pooled query is run to measure the spread (default: 3)
+//! - INDEX_CHECK: off, warn or fail when a plan doesn't use the expected index (default: warn)
+//! - PG_STAT_STATEMENTS: also record server-side execution times from pg_stat_statements
+//!
+//! Each scenario is timed without decryption, with the whole result decrypted
+//! at once, and streamed with rows decrypted as they arrive. Criterion records
+//! the total time of streamed queries (`{name}_stream_decrypt`), and the time
+//! to the first decrypted row in the same runs is written to
+//! `results/query/first_row_{name}_rows_{n}.json`.
+//!
+//! Before timing, each query in a scenario's pool is run once under `EXPLAIN
+//! (ANALYZE, BUFFERS, FORMAT JSON)` and the plans are written to
+//! `results/query/plan_{name}_rows_{n}.json`. Scenarios with a query whose
+//! plan doesn't use their `expect_index` are flagged, or fail the bench with
+//! `INDEX_CHECK=fail`.
+//!
+//! With `PG_STAT_STATEMENTS=true`, `pg_stat_statements` is reset before each
+//! scenario and read after it, and the server-side statistics are written to
+//! `results/query/server_{name}_rows_{n}.json`.
+//!
+//! Scenarios with a `baseline` also time it on the plaintext table of the
+//! same size as `{name}_plaintext`, with the same parameters unencrypted.
+//!
+//! Scenarios with a parameter `distribution` build a pool of queries and
+//! rotate through them, one per iteration. Before timing, each query in the
+//! pool is also run on its own and the spread is written to
+//! `results/query/pool_{name}_rows_{n}.json`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dbbenches::{
+    init_scoped_cipher, init_stat_statements, reset_stat_statements, stat_statements_enabled,
+    verify_enabled, FirstRowReport, FirstRowStats, IndexCheck, Latencies, PlanReport, PoolOptions,
+    PoolReport, QueryPlan, ScenarioFile, ServerStats, ServerStatsReport, VerificationReport,
+    DEFAULT_SCENARIO_DIR,
+};
+use sqlx::postgres::PgPoolOptions;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+fn scenario_paths() -> Vec<PathBuf> {
+    match std::env::var("SCENARIO") {
+        Ok(paths) => paths.split(',').map(PathBuf::from).collect(),
+        Err(_) => ScenarioFile::paths_in(DEFAULT_SCENARIO_DIR).expect("Failed to list scenarios"),
+    }
+}
+
+const DEFAULT_DECRYPT_CHUNK_SIZE: usize = 100;
+const SAMPLE_SIZE: usize = 10;
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let target_rows = std::env::var("TARGET_ROWS").unwrap_or_else(|_| "unknown".to_string());
+    let chunk_size = std::env::var("DECRYPT_CHUNK_SIZE")
+        .map(|s| {
+            s.parse()
+                .expect("DECRYPT_CHUNK_SIZE must be a valid integer")
+        })
+        .unwrap_or(DEFAULT_DECRYPT_CHUNK_SIZE);
+    let pool_options = PoolOptions::from_env().expect("Invalid parameter pool options");
+    let index_check = IndexCheck::from_env().expect("Invalid INDEX_CHECK");
+    let stat_statements = stat_statements_enabled();
+
+    let (pool, cipher) = rt.block_on(async {
+        let database_url =
+            std::env::var("DATABASE_URL").expect("DATABASE_URL environment variable must be set");
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect(&database_url)
+            .await
+            .expect("Failed to connect to database");
+
+        let cipher = init_scoped_cipher()
+            .await
+            .expect("Failed to initialize cipher");
+
+        if stat_statements {
+            init_stat_statements(&pool)
+                .await
+                .expect("Failed to initialize pg_stat_statements");
+        }
+
+        (pool, cipher)
+    });
+
+    for path in scenario_paths() {
+        let file = ScenarioFile::load(&path).expect("Failed to load scenario file");
+        let table_name = file.table_name(&target_rows);
+        let plaintext_table = file.plaintext_table_name(&target_rows);
+
+        let query_pools = rt
+            .block_on(file.build_queries(&pool, &target_rows, Arc::clone(&cipher), &pool_options))
+            .expect("Failed to build encrypted queries");
+
+        if verify_enabled() {
+            rt.block_on(async {
+                let mut report = VerificationReport::new(&file.name, &target_rows);
+                for (queries, scenario) in query_pools.iter().zip(&file.scenarios) {
+                    let verifications = file
+                        .verify(scenario, queries, &pool, &plaintext_table)
+                        .await
+                        .expect("Failed to verify query");
+                    if verifications.is_empty() {
+                        println!("SKIP  {}/{}: no predicate", file.name, scenario.name);
+                    }
+                    for verification in verifications {
+                        report.push(verification);
+                    }
+                }
+                let path = report.write().expect("Failed to write verification report");
+                println!("Verification report written to {}", path.display());
+                assert!(
+                    report.is_correct(),
+                    "Encrypted results don't match {plaintext_table}"
+                );
+            });
+            continue;
+        }
+
+        rt.block_on(async {
+            let mut report = PlanReport::new(&file.name, &target_rows);
+            for (queries, scenario) in query_pools.iter().zip(&file.scenarios) {
+                let expected_index = match index_check {
+                    IndexCheck::Off => None,
+                    _ => scenario.expected_index(&table_name),
+                };
+                let plan = QueryPlan::explain_pool(
+                    &scenario.name,
+                    &queries.queries,
+                    &pool,
+                    expected_index,
+                )
+                .await
+                .expect("Failed to explain query");
+                report.push(plan);
+            }
+            let path = report.write().expect("Failed to write query plans");
+            println!("Query plans written to {}", path.display());
+
+            let missed: Vec<&str> = report
+                .missed_indexes()
+                .map(|p| p.scenario.as_str())
+                .collect();
+            assert!(
+                index_check != IndexCheck::Fail || missed.is_empty(),
+                "Scenarios don't use their expected index: {}",
+                missed.join(", ")
+            );
+        });
+
+        rt.block_on(async {
+            let mut report = PoolReport::new(&file.name, &target_rows, pool_options.seed);
+            for (queries, scenario) in query_pools.iter().zip(&file.scenarios) {
+                if queries.len() > 1 {
+                    let profile = file
+                        .profile_pool(scenario, queries, &pool, pool_options.profile_runs)
+                        .await
+                        .expect("Failed to profile parameter pool");
+                    report.push(profile);
+                }
+            }
+            if !report.is_empty() {
+                let path = report.write().expect("Failed to write pool report");
+                println!("Pool report written to {}", path.display());
+            }
+        });
+
+        let mut group = c.benchmark_group(&file.group);
+        group.sample_size(SAMPLE_SIZE);
+
+        let mut server_stats = ServerStatsReport::new(&file.name, &target_rows);
+        let mut first_rows = FirstRowReport::new(&file.name, &target_rows);
+
+        for (queries, scenario) in query_pools.iter().zip(&file.scenarios) {
+            if stat_statements {
+                rt.block_on(reset_stat_statements(&pool))
+                    .expect("Failed to reset pg_stat_statements");
+            }
+
+            group.bench_function(
+                format!("{}/{}/{}", file.name, scenario.name, target_rows),
+                |b| {
+                    let pool = &pool;
+                    let mut queries = queries.cycle();
+                    b.to_async(&rt).iter(|| {
+                        let query = queries.next().unwrap();
+                        async move {
+                            let _: Vec<_> = query.execute(pool).await.unwrap();
+                        }
+                    })
+                },
+            );
+
+            group.bench_function(
+                format!("{}_decrypt/{}/{}", file.name, scenario.name, target_rows),
+                |b| {
+                    let pool = &pool;
+                    let mut queries = queries.cycle();
+                    b.to_async(&rt).iter(|| {
+                        let query = queries.next().unwrap();
+                        async move {
+                            black_box(file.decrypt.execute_and_decrypt(query, pool).await.unwrap());
+                        }
+                    })
+                },
+            );
+
+            // The first row latencies of each call, warmup included
+            let first_row_runs: Mutex<Vec<Latencies>> = Mutex::new(Vec::new());
+            group.bench_function(
+                format!(
+                    "{}_stream_decrypt/{}/{}",
+                    file.name, scenario.name, target_rows
+                ),
+                |b| {
+                    let pool = &pool;
+                    let first_row_runs = &first_row_runs;
+                    let mut queries = queries.cycle();
+                    b.to_async(&rt).iter_custom(|iters| {
+                        let queries: Vec<_> = queries.by_ref().take(iters as usize).collect();
+                        async move {
+                            let mut total = Duration::ZERO;
+                            let mut first_row = Latencies::new();
+                            for query in queries {
+                                let timing = file
+                                    .decrypt
+                                    .time_fetch_and_decrypt(query, pool, chunk_size)
+                                    .await
+                                    .unwrap();
+                                total += timing.total;
+                                first_row.record(timing.first_row);
+                            }
+                            first_row_runs.lock().unwrap().push(first_row);
+                            total
+                        }
+                    })
+                },
+            );
+
+            // Criterion measures one call per sample after the warmup calls
+            let first_row_runs = first_row_runs.into_inner().unwrap();
+            if !first_row_runs.is_empty() {
+                let mut first_row = Latencies::new();
+                for run in first_row_runs.iter().rev().take(SAMPLE_SIZE) {
+                    first_row.add(run);
+                }
+                first_rows.push(FirstRowStats::new(&scenario.name, &first_row));
+            }
+
+            if stat_statements {
+                let stats = rt
+                    .block_on(ServerStats::read(&pool, &scenario.name))
+                    .expect("Failed to read pg_stat_statements");
+                // Nothing ran if criterion filtered the scenario out
+                if let Some(stats) = stats {
+                    server_stats.push(stats);
+                }
+            }
+
+            if !queries.baselines.is_empty() {
+                group.bench_function(
+                    format!("{}_plaintext/{}/{}", file.name, scenario.name, target_rows),
+                    |b| {
+                        let pool = &pool;
+                        let mut baselines = queries.cycle_baselines();
+                        b.to_async(&rt).iter(|| {
+                            let baseline = baselines.next().unwrap();
+                            async move {
+                                black_box(baseline.execute(pool).await.unwrap());
+                            }
+                        })
+                    },
+                );
+            }
+        }
+
+        group.finish();
+
+        if !first_rows.is_empty() {
+            let path = first_rows
+                .write()
+                .expect("Failed to write first row report");
+            println!("First row latencies written to {}", path.display());
+        }
+
+        if !server_stats.is_empty() {
+            let path = server_stats
+                .write()
+                .expect("Failed to write pg_stat_statements report");
+            println!("Server statistics written to {}", path.display());
+        }
+    }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
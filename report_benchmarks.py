@@ -45,6 +45,25 @@ class QueryResult:
     decrypt: bool
     mean_ns: float
     median_ns: float
+    variant: str = ""  # "", "decrypt", "stream_decrypt", "first_row" or "plaintext"
+
+
+@dataclass
+class MicroResult:
+    """Results from a database-free microbenchmark"""
+    group: str  # e.g., "ENCRYPT_STORE"
+    name: str  # e.g., "match"
+    payload: str  # e.g., "string_256", or "-"
+    records: int  # records per iteration
+    mean_ns: float
+
+    @property
+    def ns_per_record(self) -> float:
+        return self.mean_ns / max(self.records, 1)
+
+    @property
+    def throughput(self) -> float:
+        return self.records / (self.mean_ns / 1_000_000_000) if self.mean_ns else 0.0
 
 
 class BenchmarkReporter:
@@ -54,7 +73,22 @@ class BenchmarkReporter:
         self.sql_dir = sql_dir or Path("sql")
         self.ingest_results: List[IngestResult] = []
         self.query_results: List[QueryResult] = []
+        self.micro_results: List[MicroResult] = []
+        # Table sizes from results/storage/{table}.json, keyed by table name
+        self.storage: Dict[str, dict] = {}
+        # Concurrent load tests from results/load/load_test*.json
+        self.load_tests: List[dict] = []
+        # Open-loop rate sweeps from results/load/open_loop*.json
+        self.open_loop: List[dict] = []
+        # Mixed read/write workloads from results/load/mixed_*.json
+        self.mixed: List[dict] = []
+        # Update and delete timings from results/modify/{table}_rows_{n}.json
+        self.modify: List[dict] = []
         self.index_cache: Dict[str, str] = {}  # Cache for index SQL
+        # Plan summaries from plan_{query_type}_rows_{count}.json, keyed by (query_type, scenario, rows)
+        self.query_plans: Dict[Tuple[str, str, int], dict] = {}
+        # pg_stat_statements from server_{query_type}_rows_{count}.json, keyed the same way
+        self.server_stats: Dict[Tuple[str, str, int], dict] = {}
 
     def load_ingest_results(self):
         """Load ingest benchmark results"""
@@ -91,6 +125,19 @@ class BenchmarkReporter:
             
             query_type = parts[0].upper()  # EXACT, MATCH, ORE
             row_count = int(parts[1])
+
+            if query_type.startswith("PLAN_"):
+                self.load_query_plans(json_file, query_type[len("PLAN_"):], row_count)
+                continue
+            if query_type.startswith("SERVER_"):
+                self.load_server_stats(json_file, query_type[len("SERVER_"):], row_count)
+                continue
+            if query_type.startswith("FIRST_ROW_"):
+                self.load_first_rows(json_file, query_type[len("FIRST_ROW_"):], row_count)
+                continue
+            if query_type.startswith(("VERIFY_", "POOL_")):
+                # Written by the query bench alongside the criterion output
+                continue
             
             with open(json_file) as f:
                 for line in f:
@@ -113,7 +160,9 @@ class BenchmarkReporter:
                     if len(parts) < 3:
                         continue
                     
-                    # Determine if this is a decrypt variant
+                    # Determine the variant: "ore", "ore_decrypt", "ore_stream_decrypt", "ore_first_row", "ore_plaintext"
+                    variant = next((v for v in ("stream_decrypt", "first_row", "decrypt", "plaintext")
+                                    if parts[1].endswith(f"_{v}")), "")
                     decrypt = "decrypt" in parts[1]
                     
                     # Get scenario name (e.g., "eql_cast", "range_gt_10")
@@ -129,9 +178,135 @@ class BenchmarkReporter:
                         row_count=row_count,
                         decrypt=decrypt,
                         mean_ns=mean_ns,
-                        median_ns=median_ns
+                        median_ns=median_ns,
+                        variant=variant
+                    ))
+
+    def load_query_plans(self, json_file: Path, query_type: str, row_count: int):
+        """Load the EXPLAIN summaries written alongside the criterion output"""
+        with open(json_file) as f:
+            report = json.load(f)
+        for plan in report.get("scenarios", []):
+            self.query_plans[(query_type, plan["scenario"], row_count)] = plan
+
+    def load_server_stats(self, json_file: Path, query_type: str, row_count: int):
+        """Load the pg_stat_statements summaries written alongside the criterion output"""
+        with open(json_file) as f:
+            report = json.load(f)
+        for stats in report.get("scenarios", []):
+            self.server_stats[(query_type, stats["scenario"], row_count)] = stats
+
+    def load_first_rows(self, json_file: Path, query_type: str, row_count: int):
+        """Load the time to the first streamed row, taken from the stream_decrypt runs"""
+        with open(json_file) as f:
+            report = json.load(f)
+        for stats in report.get("scenarios", []):
+            first_row = stats["first_row"]
+            self.query_results.append(QueryResult(
+                query_type=query_type,
+                query_name=stats["scenario"],
+                row_count=row_count,
+                decrypt=True,
+                mean_ns=first_row["mean_ms"] * 1e6,
+                median_ns=first_row["p50_ms"] * 1e6,
+                variant="first_row"
+            ))
+
+    def format_plan(self, plan: dict) -> str:
+        scans = []
+        for scan in plan.get("scans", []):
+            if scan.get("index"):
+                scans.append(f"{scan['node_type']} using `{scan['index']}`")
+            elif scan.get("relation"):
+                scans.append(f"{scan['node_type']} on `{scan['relation']}`")
+            else:
+                scans.append(scan["node_type"])
+        text = ", ".join(scans) or "No scans"
+        if plan.get("expected_index_used") is False:
+            missed = ""
+            if plan.get("queries_explained", 1) > 1:
+                missed = f", missed by {plan['queries_missing_index']} of {plan['queries_explained']} queries"
+            return f"❌ {text} (expected `{plan['expected_index']}`{missed})"
+        if any(scan["node_type"] == "Seq Scan" for scan in plan.get("scans", [])):
+            text = f"⚠️ {text}"
+        return text
+
+    def load_micro_results(self):
+        """Load microbenchmark results from criterion JSON output in results/micro"""
+        micro_dir = self.results_dir / "micro"
+        if not micro_dir.exists():
+            return
+
+        for json_file in sorted(micro_dir.glob("*.json")):
+            with open(json_file) as f:
+                for line in f:
+                    try:
+                        data = json.loads(line)
+                    except json.JSONDecodeError:
+                        continue
+                    if not isinstance(data, dict) or data.get("reason") != "benchmark-complete":
+                        continue
+
+                    # Benchmark ID: "GROUP/name/payload/records" or "GROUP/name/records"
+                    parts = data.get("id", "").split("/")
+                    if len(parts) not in (3, 4):
+                        continue
+                    records = next((t.get("per_iteration", 1) for t in data.get("throughput") or []
+                                    if t.get("unit") == "elements"), int(parts[-1]))
+
+                    self.micro_results.append(MicroResult(
+                        group=parts[0],
+                        name=parts[1],
+                        payload=parts[2] if len(parts) == 4 else "-",
+                        records=records,
+                        mean_ns=data.get("mean", {}).get("estimate", 0),
                     ))
 
+    def load_storage_results(self):
+        """Load table sizes written by the storage_report binary"""
+        storage_dir = self.results_dir / "storage"
+        if not storage_dir.exists():
+            return
+
+        for json_file in sorted(storage_dir.glob("*.json")):
+            with open(json_file) as f:
+                data = json.load(f)
+            self.storage[data["table"]] = data
+
+    def load_modify_results(self):
+        """Load update and delete timings written by the modify_bench binary"""
+        modify_dir = self.results_dir / "modify"
+        if not modify_dir.exists():
+            return
+
+        for json_file in sorted(modify_dir.glob("*.json")):
+            with open(json_file) as f:
+                self.modify.append(json.load(f))
+
+    def load_load_tests(self):
+        """Load concurrent load test results written by the load_test binary"""
+        load_dir = self.results_dir / "load"
+        if not load_dir.exists():
+            return
+
+        for json_file in sorted(load_dir.glob("load_test*.json")):
+            with open(json_file) as f:
+                self.load_tests.append(json.load(f))
+        for json_file in sorted(load_dir.glob("open_loop*.json")):
+            with open(json_file) as f:
+                self.open_loop.append(json.load(f))
+        for json_file in sorted(load_dir.glob("mixed_*.json")):
+            with open(json_file) as f:
+                self.mixed.append(json.load(f))
+
+    def format_bytes(self, num_bytes: float) -> str:
+        """Format a byte count with a binary unit"""
+        for unit in ["B", "KiB", "MiB", "GiB"]:
+            if abs(num_bytes) < 1024:
+                return f"{num_bytes:.0f} {unit}" if unit == "B" else f"{num_bytes:.1f} {unit}"
+            num_bytes /= 1024
+        return f"{num_bytes:.1f} TiB"
+
     def format_time(self, ns: float, include_indicator: bool = True) -> str:
         """Format nanoseconds into human-readable time with performance indicator
         
@@ -220,6 +395,70 @@ class BenchmarkReporter:
                     "SELECT id,value::jsonb FROM {TABLE} WHERE value < $1 ORDER BY value LIMIT 10",
                     "5000"
                 )
+            },
+            "JSON_SMALL": {
+                "select_field": (
+                    "SELECT id,(value -> $1::jsonb::eql_v2_encrypted)::jsonb FROM {TABLE} LIMIT 10",
+                    "$.last_name"
+                ),
+                "path_exists": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE eql_v2.jsonb_path_exists(value, $1::jsonb::eql_v2_encrypted) LIMIT 10",
+                    "$.email"
+                ),
+                "path_exists_missing": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE eql_v2.jsonb_path_exists(value, $1::jsonb::eql_v2_encrypted) LIMIT 10",
+                    "$.nickname"
+                ),
+                "contains_last_name": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE value @> $1::jsonb::eql_v2_encrypted LIMIT 10",
+                    '{"last_name": "Johnson"}'
+                ),
+                "contains_age": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE value @> $1::jsonb::eql_v2_encrypted LIMIT 10",
+                    '{"age": 42}'
+                ),
+                "age_gt_65": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE (value -> $1::jsonb::eql_v2_encrypted) > $2::jsonb LIMIT 10",
+                    "$.age, 65"
+                ),
+                "age_between_30_40": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE (value -> $1::jsonb::eql_v2_encrypted) >= $2::jsonb AND (value -> $1::jsonb::eql_v2_encrypted) < $3::jsonb LIMIT 10",
+                    "$.age, 30, 40"
+                )
+            },
+            "JSON_LARGE": {
+                "select_field": (
+                    "SELECT id,(value -> $1::jsonb::eql_v2_encrypted)::jsonb FROM {TABLE} LIMIT 10",
+                    "$.user.last_name"
+                ),
+                "select_object": (
+                    "SELECT id,(value -> $1::jsonb::eql_v2_encrypted)::jsonb FROM {TABLE} LIMIT 10",
+                    "$.company"
+                ),
+                "path_exists": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE eql_v2.jsonb_path_exists(value, $1::jsonb::eql_v2_encrypted) LIMIT 10",
+                    "$.user.contact.emergency_contact.relationship"
+                ),
+                "path_exists_missing": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE eql_v2.jsonb_path_exists(value, $1::jsonb::eql_v2_encrypted) LIMIT 10",
+                    "$.user.nickname"
+                ),
+                "contains_department": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE value @> $1::jsonb::eql_v2_encrypted LIMIT 10",
+                    '{"company": {"department": "Engineering"}}'
+                ),
+                "contains_last_name": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE value @> $1::jsonb::eql_v2_encrypted LIMIT 10",
+                    '{"user": {"last_name": "Johnson"}}'
+                ),
+                "salary_gt_250000": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE (value -> $1::jsonb::eql_v2_encrypted) > $2::jsonb LIMIT 10",
+                    "$.company.salary, 250000"
+                ),
+                "age_between_30_40": (
+                    "SELECT id,value::jsonb FROM {TABLE} WHERE (value -> $1::jsonb::eql_v2_encrypted) >= $2::jsonb AND (value -> $1::jsonb::eql_v2_encrypted) < $3::jsonb LIMIT 10",
+                    "$.user.age, 30, 40"
+                )
             }
         }
         
@@ -297,6 +536,85 @@ class BenchmarkReporter:
                     "Index: ORE index supporting equality and range queries. "
                     "Query: WHERE value < 5000 ORDER BY value LIMIT 10."
                 )
+            },
+            "JSON_SMALL": {
+                "select_field": (
+                    "Field selection by encrypted path",
+                    "Table: `json_small_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Returns the selected field of 10 rows."
+                ),
+                "path_exists": (
+                    "Path existence check with `eql_v2.jsonb_path_exists`",
+                    "Table: `json_small_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                ),
+                "path_exists_missing": (
+                    "Path existence check for a path no document has",
+                    "Table: `json_small_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Every row is checked and none match."
+                ),
+                "contains_last_name": (
+                    "Containment (`@>`) of a string field",
+                    "Table: `json_small_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                ),
+                "contains_age": (
+                    "Containment (`@>`) of a numeric field",
+                    "Table: `json_small_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                ),
+                "age_gt_65": (
+                    "Range query (greater than) on a selected numeric field",
+                    "Table: `json_small_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                ),
+                "age_between_30_40": (
+                    "Range query (between) on a selected numeric field",
+                    "Table: `json_small_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                )
+            },
+            "JSON_LARGE": {
+                "select_field": (
+                    "Field selection by encrypted nested path",
+                    "Table: `json_large_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Returns the selected field of 10 rows."
+                ),
+                "select_object": (
+                    "Object selection by encrypted path",
+                    "Table: `json_large_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Returns the selected object of 10 rows."
+                ),
+                "path_exists": (
+                    "Nested path existence check with `eql_v2.jsonb_path_exists`",
+                    "Table: `json_large_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                ),
+                "path_exists_missing": (
+                    "Path existence check for a path no document has",
+                    "Table: `json_large_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Every row is checked and none match."
+                ),
+                "contains_department": (
+                    "Containment (`@>`) of a nested string field",
+                    "Table: `json_large_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                ),
+                "contains_last_name": (
+                    "Containment (`@>`) of a nested string field",
+                    "Table: `json_large_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                ),
+                "salary_gt_250000": (
+                    "Range query (greater than) on a selected nested numeric field",
+                    "Table: `json_large_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                ),
+                "age_between_30_40": (
+                    "Range query (between) on a selected nested numeric field",
+                    "Table: `json_large_encrypted_{rows}` with SteVec-encrypted JSON documents. "
+                    "No index, the table is scanned. Query returns LIMIT 10 results."
+                )
             }
         }
         
@@ -335,6 +653,13 @@ class BenchmarkReporter:
             self._write_header(f)
             self._write_ingest_section(f)
             self._write_query_sections(f)
+            self._write_overhead_section(f)
+            self._write_load_test_section(f)
+            self._write_open_loop_section(f)
+            self._write_mixed_section(f)
+            self._write_modify_section(f)
+            self._write_micro_section(f)
+            self._write_storage_section(f)
             self._write_footer(f)
 
     def _write_header(self, f):
@@ -348,6 +673,28 @@ class BenchmarkReporter:
         query_types = set(r.query_type for r in self.query_results)
         for qt in sorted(query_types):
             f.write(f"   - [{qt} Queries](#{qt.lower()}-queries)\n")
+
+        section = 3
+        if self.plaintext_results():
+            f.write(f"{section}. [Encryption Overhead](#encryption-overhead)\n")
+            section += 1
+        if self.load_tests:
+            f.write(f"{section}. [Load Test](#load-test)\n")
+            section += 1
+        if self.open_loop:
+            f.write(f"{section}. [Open-Loop Rate Sweep](#open-loop-rate-sweep)\n")
+            section += 1
+        if self.mixed:
+            f.write(f"{section}. [Mixed Read/Write Workload](#mixed-readwrite-workload)\n")
+            section += 1
+        if self.modify:
+            f.write(f"{section}. [Updates and Deletes](#updates-and-deletes)\n")
+            section += 1
+        if self.micro_results:
+            f.write(f"{section}. [Microbenchmarks](#microbenchmarks)\n")
+            section += 1
+        if self.storage:
+            f.write(f"{section}. [Storage Footprint](#storage-footprint)\n")
         
         f.write("\n---\n\n")
 
@@ -433,8 +780,11 @@ class BenchmarkReporter:
 
     def _write_query_subsection(self, f, query_type: str, query_name: str):
         # Get results for this specific query
-        results = [r for r in self.query_results 
-                  if r.query_type == query_type and r.query_name == query_name]
+        all_results = [r for r in self.query_results
+                       if r.query_type == query_type and r.query_name == query_name]
+        results = [r for r in all_results if r.variant in ("", "decrypt")]
+        streamed = [r for r in all_results if r.variant in ("stream_decrypt", "first_row")]
+        plaintext = [r for r in all_results if r.variant == "plaintext"]
         
         if not results:
             return
@@ -464,6 +814,8 @@ class BenchmarkReporter:
                 table_name = f"string_encrypted_{sample_row_count}"
             elif query_type == "ORE":
                 table_name = f"integer_encrypted_{sample_row_count}"
+            elif query_type in ["JSON_SMALL", "JSON_LARGE"]:
+                table_name = f"{query_type.lower()}_encrypted_{sample_row_count}"
             else:
                 table_name = ""
             
@@ -478,8 +830,12 @@ class BenchmarkReporter:
         if has_slow_queries:
             f.write("*⚠️ = Query time exceeds 100ms*\n\n")
         
-        f.write("| Data Set Size | Query Time (no decrypt) | Query Time (with decrypt) |\n")
-        f.write("|---------------|-------------------------|---------------------------|\n")
+        if plaintext:
+            f.write("| Data Set Size | Plaintext | Query Time (no decrypt) | Query Time (with decrypt) | Overhead (no decrypt) | Overhead (with decrypt) |\n")
+            f.write("|---------------|-----------|-------------------------|---------------------------|-----------------------|-------------------------|\n")
+        else:
+            f.write("| Data Set Size | Query Time (no decrypt) | Query Time (with decrypt) |\n")
+            f.write("|---------------|-------------------------|---------------------------|\n")
         
         # Group by row_count
         row_counts = sorted(set(r.row_count for r in results))
@@ -491,14 +847,62 @@ class BenchmarkReporter:
             no_decrypt_str = self.format_time(no_decrypt.mean_ns) if no_decrypt else "N/A"
             with_decrypt_str = self.format_time(with_decrypt.mean_ns) if with_decrypt else "N/A"
             
-            f.write(f"| {row_count:,} | {no_decrypt_str} | {with_decrypt_str} |\n")
+            if plaintext:
+                base = next((r for r in plaintext if r.row_count == row_count), None)
+                base_str = self.format_time(base.mean_ns) if base else "N/A"
+                f.write(f"| {row_count:,} | {base_str} | {no_decrypt_str} | {with_decrypt_str} | "
+                        f"{self.format_overhead(no_decrypt, base)} | {self.format_overhead(with_decrypt, base)} |\n")
+            else:
+                f.write(f"| {row_count:,} | {no_decrypt_str} | {with_decrypt_str} |\n")
         
         f.write("\n")
+
+        if streamed:
+            f.write("Streamed with rows decrypted as they arrive:\n\n")
+            f.write("| Data Set Size | Time to First Row | Total Time |\n")
+            f.write("|---------------|-------------------|------------|\n")
+            for row_count in sorted(set(r.row_count for r in streamed)):
+                first_row = next((r for r in streamed if r.row_count == row_count and r.variant == "first_row"), None)
+                total = next((r for r in streamed if r.row_count == row_count and r.variant == "stream_decrypt"), None)
+                first_row_str = self.format_time(first_row.mean_ns) if first_row else "N/A"
+                total_str = self.format_time(total.mean_ns) if total else "N/A"
+                f.write(f"| {row_count:,} | {first_row_str} | {total_str} |\n")
+            f.write("\n")
+
+        plans = [(row_count, self.query_plans[(query_type, query_name, row_count)])
+                 for row_count in row_counts
+                 if (query_type, query_name, row_count) in self.query_plans]
+        if plans:
+            f.write("Query plan (`EXPLAIN ANALYZE` with the encrypted parameters):\n\n")
+            f.write("| Data Set Size | Scans | Execution Time | Shared Hits | Shared Reads |\n")
+            f.write("|---------------|-------|----------------|-------------|--------------|\n")
+            for row_count, plan in plans:
+                execution = self.format_time(plan["execution_ms"] * 1_000_000, include_indicator=False)
+                f.write(f"| {row_count:,} | {self.format_plan(plan)} | {execution} | "
+                        f"{plan['shared_hit_blocks']:,} | {plan['shared_read_blocks']:,} |\n")
+            f.write("\n")
         
+        server = [(row_count, self.server_stats[(query_type, query_name, row_count)])
+                  for row_count in row_counts
+                  if (query_type, query_name, row_count) in self.server_stats]
+        if server:
+            f.write("Server-side execution time (`pg_stat_statements`) against the client-side time without decryption:\n\n")
+            f.write("| Data Set Size | Server Mean | Server Stddev | Server Max | Client Mean | Rows/Call | Shared Hits/Call | Shared Reads/Call |\n")
+            f.write("|---------------|-------------|---------------|------------|-------------|-----------|------------------|-------------------|\n")
+            for row_count, stats in server:
+                calls = max(stats["calls"], 1)
+                client = next((r for r in results if r.row_count == row_count and not r.decrypt), None)
+                client_str = self.format_time(client.mean_ns, include_indicator=False) if client else "N/A"
+                ms = lambda v: self.format_time(v * 1_000_000, include_indicator=False)
+                f.write(f"| {row_count:,} | {ms(stats['mean_ms'])} | {ms(stats['stddev_ms'])} | "
+                        f"{ms(stats['max_ms'])} | {client_str} | {stats['rows'] / calls:,.1f} | "
+                        f"{stats['shared_blks_hit'] / calls:,.1f} | {stats['shared_blks_read'] / calls:,.1f} |\n")
+            f.write("\n")
+
         # Generate chart if matplotlib is available
         if HAS_MATPLOTLIB and len(row_counts) > 1:
             chart_path = self.output_file.parent / f"query_{query_type.lower()}_{query_name}_chart.png"
-            self._create_query_chart(results, query_type, query_name, chart_path)
+            self._create_query_chart(results + plaintext, query_type, query_name, chart_path)
             f.write(f"![Query Performance - {query_type}/{query_name}]({chart_path.name})\n\n")
 
     def _create_query_chart(self, results: List[QueryResult], query_type: str, 
@@ -511,16 +915,22 @@ class BenchmarkReporter:
         
         no_decrypt_times = []
         with_decrypt_times = []
+        plaintext_times = []
         
         for row_count in row_counts:
-            no_decrypt = next((r for r in results if r.row_count == row_count and not r.decrypt), None)
-            with_decrypt = next((r for r in results if r.row_count == row_count and r.decrypt), None)
+            no_decrypt = next((r for r in results if r.row_count == row_count and r.variant == ""), None)
+            with_decrypt = next((r for r in results if r.row_count == row_count and r.variant == "decrypt"), None)
+            plaintext = next((r for r in results if r.row_count == row_count and r.variant == "plaintext"), None)
             
             # Convert to milliseconds for better readability
             no_decrypt_times.append(no_decrypt.mean_ns / 1_000_000 if no_decrypt else None)
             with_decrypt_times.append(with_decrypt.mean_ns / 1_000_000 if with_decrypt else None)
+            plaintext_times.append(plaintext.mean_ns / 1_000_000 if plaintext else None)
         
         # Plot lines
+        if any(t is not None for t in plaintext_times):
+            ax.plot(row_counts, plaintext_times, marker='^', label='Plaintext', linewidth=2, linestyle='--')
+        
         if any(t is not None for t in no_decrypt_times):
             ax.plot(row_counts, no_decrypt_times, marker='o', label='Without Decryption', linewidth=2)
         
@@ -542,6 +952,232 @@ class BenchmarkReporter:
         plt.savefig(output_path, dpi=100, bbox_inches='tight')
         plt.close()
 
+    def plaintext_results(self) -> List[QueryResult]:
+        return [r for r in self.query_results if r.variant == "plaintext"]
+
+    def format_overhead(self, encrypted: Optional[QueryResult], plaintext: Optional[QueryResult]) -> str:
+        """How many times slower the encrypted query is than its plaintext baseline"""
+        if not encrypted or not plaintext or not plaintext.mean_ns:
+            return "N/A"
+        return f"{encrypted.mean_ns / plaintext.mean_ns:,.1f}x"
+
+    def _write_overhead_section(self, f):
+        baselines = self.plaintext_results()
+        if not baselines:
+            return
+
+        f.write("## Encryption Overhead\n\n")
+        f.write("Each encrypted query against its plaintext baseline: the same query on the plaintext table of ")
+        f.write("the same size, with B-tree and trigram indexes and the same parameters unencrypted.\n\n")
+        f.write("| Query | Data Set Size | Plaintext | Encrypted (no decrypt) | Encrypted (with decrypt) | Overhead (no decrypt) | Overhead (with decrypt) |\n")
+        f.write("|-------|---------------|-----------|------------------------|--------------------------|-----------------------|-------------------------|\n")
+        for base in sorted(baselines, key=lambda r: (r.query_type, r.query_name, r.row_count)):
+            encrypted = [r for r in self.query_results
+                         if (r.query_type, r.query_name, r.row_count) == (base.query_type, base.query_name, base.row_count)]
+            no_decrypt = next((r for r in encrypted if r.variant == ""), None)
+            with_decrypt = next((r for r in encrypted if r.variant == "decrypt"), None)
+            f.write(f"| {base.query_type}/{base.query_name} | {base.row_count:,} | "
+                    f"{self.format_time(base.mean_ns, include_indicator=False)} | "
+                    f"{self.format_time(no_decrypt.mean_ns, include_indicator=False) if no_decrypt else 'N/A'} | "
+                    f"{self.format_time(with_decrypt.mean_ns, include_indicator=False) if with_decrypt else 'N/A'} | "
+                    f"{self.format_overhead(no_decrypt, base)} | {self.format_overhead(with_decrypt, base)} |\n")
+        f.write("\n")
+
+    def _write_load_test_section(self, f):
+        if not self.load_tests:
+            return
+
+        f.write("## Load Test\n\n")
+        f.write("Concurrent clients each running a weighted mix of the EXACT, MATCH and ORE scenarios back to back. ")
+        f.write("Throughput is completed queries per second and latencies are from HDR histograms.\n\n")
+
+        runs = sorted(self.load_tests, key=lambda r: (int(r["target_rows"]), r["decrypt"], r["clients"]))
+        for (target_rows, decrypt) in sorted(set((int(r["target_rows"]), r["decrypt"]) for r in runs)):
+            group = [r for r in runs if int(r["target_rows"]) == target_rows and r["decrypt"] == decrypt]
+            f.write(f"### {target_rows:,} rows{' (with decrypt)' if decrypt else ''}\n\n")
+
+            f.write("| Clients | Queries/sec | Errors |\n")
+            f.write("|---------|-------------|--------|\n")
+            for r in group:
+                f.write(f"| {r['clients']} | {r['qps']:,.1f} | {r['errors']:,} |\n")
+            f.write("\n")
+
+            ms = lambda v: self.format_time(v * 1_000_000, include_indicator=False)
+            f.write("| Clients | Scenario | Weight | Queries/sec | p50 | p95 | p99 | p99.9 | Max | Errors |\n")
+            f.write("|---------|----------|--------|-------------|-----|-----|-----|-------|-----|--------|\n")
+            for r in group:
+                for s in r["scenarios"]:
+                    l = s["latency"]
+                    f.write(f"| {r['clients']} | {s['scenario']} | {s['weight']} | {s['qps']:,.1f} | "
+                            f"{ms(l['p50_ms'])} | {ms(l['p95_ms'])} | {ms(l['p99_ms'])} | "
+                            f"{ms(l['p999_ms'])} | {ms(l['max_ms'])} | {s['errors']:,} |\n")
+            f.write("\n")
+
+    def _write_open_loop_section(self, f):
+        if not self.open_loop:
+            return
+
+        f.write("## Open-Loop Rate Sweep\n\n")
+        f.write("Queries started on a fixed schedule at each target rate, whether or not earlier ones have finished. ")
+        f.write("Latency is measured from each query's scheduled start, so it includes time spent queued behind ")
+        f.write("slower queries. A rate is sustained when throughput is within 5% of it; past the highest sustained ")
+        f.write("rate, queries queue up and latency climbs steeply. The histograms are in the `.hlog` files next to ")
+        f.write("the results.\n\n")
+
+        ms = lambda v: self.format_time(v * 1_000_000, include_indicator=False)
+        key = lambda r: (r["workload"], int(r["target_rows"]), r["decrypt"], r["arrivals"])
+        for workload, target_rows, decrypt, arrivals in sorted(set(key(r) for r in self.open_loop)):
+            runs = sorted((r for r in self.open_loop if key(r) == (workload, target_rows, decrypt, arrivals)),
+                          key=lambda r: r["target_qps"])
+            f.write(f"### {workload}, {target_rows:,} rows ({arrivals} arrivals{', with decrypt' if decrypt else ''})\n\n")
+
+            sustained = [r["target_qps"] for r in runs if r["qps"] >= 0.95 * r["target_qps"]]
+            if sustained:
+                f.write(f"**Highest sustained rate:** {max(sustained):,g} queries/sec\n\n")
+
+            f.write("| Target Rate | Throughput | p50 | p95 | p99 | p99.9 | Max | Errors |\n")
+            f.write("|-------------|------------|-----|-----|-----|-------|-----|--------|\n")
+            for r in runs:
+                l = r["latency"]
+                f.write(f"| {r['target_qps']:,g}/s | {r['qps']:,.1f}/s | {ms(l['p50_ms'])} | {ms(l['p95_ms'])} | "
+                        f"{ms(l['p99_ms'])} | {ms(l['p999_ms'])} | {ms(l['max_ms'])} | {r['errors']:,} |\n")
+            f.write("\n")
+
+    def _write_mixed_section(self, f):
+        if not self.mixed:
+            return
+
+        f.write("## Mixed Read/Write Workload\n\n")
+        f.write("Concurrent clients interleaving encrypted point lookups, scans, updates and inserts at fixed ratios ")
+        f.write("on one table. Writes maintain every index on the column while reads use them, so these show how ")
+        f.write("the indexes hold up under concurrent writes. Latencies include encrypting each operation's values. ")
+        f.write("Scans on the integer table are ORE range scans; the string table has no ORE terms, so its scans are ")
+        f.write("free text matches. Mirror errors are writes that didn't reach the plaintext table, which leaves it out of ")
+        f.write("step with the encrypted one.\n\n")
+
+        ms = lambda v: self.format_time(v * 1_000_000, include_indicator=False)
+        key = lambda r: (r["table"], int(r["target_rows"]), r["mix"], r["decrypt"])
+        for table, target_rows, mix, decrypt in sorted(set(key(r) for r in self.mixed)):
+            runs = sorted((r for r in self.mixed if key(r) == (table, target_rows, mix, decrypt)),
+                          key=lambda r: r["clients"])
+            f.write(f"### {table}_encrypted_{target_rows}: {mix}{' (with decrypt)' if decrypt else ''}\n\n")
+
+            f.write("| Clients | Operations/sec | Errors |\n")
+            f.write("|---------|----------------|--------|\n")
+            for r in runs:
+                f.write(f"| {r['clients']} | {r['ops_per_sec']:,.1f} | {r['errors']:,} |\n")
+            f.write("\n")
+
+            f.write("| Clients | Operation | Ratio | Operations/sec | Rows/op | p50 | p95 | p99 | p99.9 | Max | Errors | Mirror Errors |\n")
+            f.write("|---------|-----------|-------|----------------|---------|-----|-----|-----|-------|-----|--------|---------------|\n")
+            for r in runs:
+                for o in r["operations"]:
+                    l = o["latency"]
+                    f.write(f"| {r['clients']} | {o['operation']} | {o['ratio']} | {o['ops_per_sec']:,.1f} | "
+                            f"{o['rows_per_op']:,.1f} | {ms(l['p50_ms'])} | {ms(l['p95_ms'])} | {ms(l['p99_ms'])} | "
+                            f"{ms(l['p999_ms'])} | {ms(l['max_ms'])} | {o['errors']:,} | {o.get('mirror_errors', 0):,} |\n")
+            f.write("\n")
+
+    def _write_modify_section(self, f):
+        if not self.modify:
+            return
+
+        f.write("## Updates and Deletes\n\n")
+        f.write("Updates re-encrypt a row's value and add an entry for it to every index on the column. They're timed ")
+        f.write("with no indexes, with each index alone and with all of them; the index cost is the extra time over ")
+        f.write("no indexes. Deletes don't touch indexes (vacuum cleans them up later), so they're timed with every ")
+        f.write("index in place, where their cost is finding the rows. Each operation runs in a transaction that is ")
+        f.write("rolled back, and times are for running the statement, with encryption shown separately.\n\n")
+
+        ms = lambda v: self.format_time(v * 1_000_000, include_indicator=False)
+        for table in sorted(set(r["table"] for r in self.modify)):
+            reports = sorted((r for r in self.modify if r["table"] == table), key=lambda r: int(r["target_rows"]))
+            f.write(f"### {table}_encrypted\n\n")
+
+            sets = []
+            for r in reports:
+                for res in r["results"]:
+                    if res["operation"] == "update" and res["indexes"] not in sets:
+                        sets.append(res["indexes"])
+            if sets:
+                f.write("Mean update time by the indexes on the column, with the index cost over no indexes:\n\n")
+                f.write("| Rows | " + " | ".join(sets) + " |\n")
+                f.write("|------|" + "|".join("-" * (len(s) + 2) for s in sets) + "|\n")
+                for r in reports:
+                    updates = {res["indexes"]: res for res in r["results"] if res["operation"] == "update"}
+                    none = updates.get("none")
+                    cells = []
+                    for name in sets:
+                        res = updates.get(name)
+                        if res is None:
+                            cells.append("-")
+                        elif none is None or name == "none":
+                            cells.append(ms(res["execute"]["mean_ms"]))
+                        else:
+                            cost = res["execute"]["mean_ms"] - none["execute"]["mean_ms"]
+                            cells.append(f"{ms(res['execute']['mean_ms'])} ({'+' if cost >= 0 else '-'}{ms(abs(cost))})")
+                    f.write(f"| {int(r['target_rows']):,} | " + " | ".join(cells) + " |\n")
+                f.write("\n")
+
+            f.write("| Rows | Operation | Indexes | p50 | Mean | p99 | Max | Encrypt (mean) | Rows/op |\n")
+            f.write("|------|-----------|---------|-----|------|-----|-----|----------------|---------|\n")
+            for r in reports:
+                for res in r["results"]:
+                    e = res["execute"]
+                    f.write(f"| {int(r['target_rows']):,} | {res['operation']} | {res['indexes']} | "
+                            f"{ms(e['p50_ms'])} | {ms(e['mean_ms'])} | {ms(e['p99_ms'])} | {ms(e['max_ms'])} | "
+                            f"{ms(res['encrypt']['mean_ms'])} | {res['rows_per_op']:,.1f} |\n")
+            f.write("\n")
+
+    def _write_micro_section(self, f):
+        if not self.micro_results:
+            return
+
+        f.write("## Microbenchmarks\n\n")
+        f.write("These benchmarks run without a database, timing the cryptography on its own.\n\n")
+
+        for group in sorted(set(r.group for r in self.micro_results)):
+            results = [r for r in self.micro_results if r.group == group]
+            f.write(f"### {group}\n\n")
+            f.write("| Benchmark | Payload | Batch | Time per Batch | Time per Record | Throughput (records/sec) |\n")
+            f.write("|-----------|---------|-------|----------------|-----------------|--------------------------|\n")
+            for r in results:
+                f.write(f"| {r.name} | {r.payload} | {r.records:,} | "
+                        f"{self.format_time(r.mean_ns, include_indicator=False)} | "
+                        f"{self.format_time(r.ns_per_record, include_indicator=False)} | "
+                        f"{self.format_throughput(r.throughput)} |\n")
+            f.write("\n")
+
+    def _write_storage_section(self, f):
+        if not self.storage:
+            return
+
+        f.write("## Storage Footprint\n\n")
+        f.write("Sizes of the prepared tables. Totals include TOAST and indexes, and the average value size is ")
+        f.write("the stored `value` column after compression. The overhead compares each encrypted table's total ")
+        f.write("size with its plaintext counterpart.\n\n")
+        f.write("| Table | Rows | Total | Heap | TOAST | Indexes | Bytes/Row | Avg Value | Avg Value (text) | Overhead |\n")
+        f.write("|-------|------|-------|------|-------|---------|-----------|-----------|------------------|----------|\n")
+        for table, s in sorted(self.storage.items()):
+            plaintext = self.storage.get(table.replace("_encrypted", "_plaintext"))
+            overhead = "-"
+            if "_encrypted" in table and plaintext and plaintext["total_bytes"]:
+                overhead = f"{s['total_bytes'] / plaintext['total_bytes']:.1f}x"
+            f.write(f"| {table} | {s['rows']:,} | {self.format_bytes(s['total_bytes'])} | "
+                    f"{self.format_bytes(s['relation_bytes'])} | {self.format_bytes(s['toast_bytes'])} | "
+                    f"{self.format_bytes(s['indexes_bytes'])} | {s['total_bytes'] / max(s['rows'], 1):,.0f} | "
+                    f"{s['avg_value_bytes']:,.0f} B | {s['avg_value_text_bytes']:,.0f} B | {overhead} |\n")
+        f.write("\n")
+
+        f.write("### Index Sizes\n\n")
+        f.write("| Table | Index | Size | Bytes/Row |\n")
+        f.write("|-------|-------|------|-----------|\n")
+        for table, s in sorted(self.storage.items()):
+            for index in s.get("indexes", []):
+                f.write(f"| {table} | `{index['name']}` | {self.format_bytes(index['bytes'])} | "
+                        f"{index['bytes'] / max(s['rows'], 1):,.0f} |\n")
+        f.write("\n")
+
     def _write_footer(self, f):
         f.write("\n---\n\n")
         f.write("*Report generated by `report_benchmarks.py`*\n")
@@ -574,6 +1210,23 @@ def main():
     print("Loading query results...")
     reporter.load_query_results()
     print(f"  Found {len(reporter.query_results)} query results")
+
+    print("Loading microbenchmark results...")
+    reporter.load_micro_results()
+    print(f"  Found {len(reporter.micro_results)} microbenchmark results")
+
+    print("Loading load test results...")
+    reporter.load_load_tests()
+    print(f"  Found {len(reporter.load_tests)} load test, {len(reporter.open_loop)} open-loop "
+          f"and {len(reporter.mixed)} mixed workload results")
+
+    print("Loading update and delete results...")
+    reporter.load_modify_results()
+    print(f"  Found {len(reporter.modify)} tables")
+
+    print("Loading storage results...")
+    reporter.load_storage_results()
+    print(f"  Found {len(reporter.storage)} table sizes")
     
     print(f"Generating report: {args.output}")
     reporter.generate_report()
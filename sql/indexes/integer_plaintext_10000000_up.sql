@@ -0,0 +1,5 @@
+CREATE INDEX
+integer_plaintext_10000000_btree_index
+ON integer_plaintext_10000000 (
+    value
+);
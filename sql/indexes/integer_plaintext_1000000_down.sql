@@ -0,0 +1 @@
+DROP INDEX IF EXISTS integer_plaintext_1000000_btree_index;
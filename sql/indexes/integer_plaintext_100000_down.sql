@@ -0,0 +1 @@
+DROP INDEX IF EXISTS integer_plaintext_100000_btree_index;
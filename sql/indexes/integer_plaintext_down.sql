@@ -0,0 +1 @@
+DROP INDEX IF EXISTS integer_plaintext_btree_index;
@@ -0,0 +1,5 @@
+CREATE INDEX
+integer_plaintext_btree_index
+ON integer_plaintext (
+    value
+);
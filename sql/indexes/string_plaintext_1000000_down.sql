@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS string_plaintext_1000000_btree_index;
+DROP INDEX IF EXISTS string_plaintext_1000000_trgm_index;
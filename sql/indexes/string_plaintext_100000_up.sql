@@ -0,0 +1,11 @@
+CREATE INDEX
+string_plaintext_100000_btree_index
+ON string_plaintext_100000 (
+    value
+);
+
+CREATE INDEX
+string_plaintext_100000_trgm_index
+ON string_plaintext_100000 USING GIN (
+    value gin_trgm_ops
+);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS string_plaintext_btree_index;
+DROP INDEX IF EXISTS string_plaintext_trgm_index;
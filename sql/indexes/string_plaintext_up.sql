@@ -0,0 +1,11 @@
+CREATE INDEX
+string_plaintext_btree_index
+ON string_plaintext (
+    value
+);
+
+CREATE INDEX
+string_plaintext_trgm_index
+ON string_plaintext USING GIN (
+    value gin_trgm_ops
+);
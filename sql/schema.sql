@@ -1,8 +1,31 @@
+-- Trigram indexes on the plaintext string tables, for the LIKE baselines
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
 CREATE TABLE IF NOT EXISTS integer_plaintext (
     id SERIAL PRIMARY KEY,
     value INT NOT NULL
 );
 
+CREATE TABLE IF NOT EXISTS integer_plaintext_10000 (
+    id SERIAL PRIMARY KEY,
+    value INT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS integer_plaintext_100000 (
+    id SERIAL PRIMARY KEY,
+    value INT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS integer_plaintext_1000000 (
+    id SERIAL PRIMARY KEY,
+    value INT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS integer_plaintext_10000000 (
+    id SERIAL PRIMARY KEY,
+    value INT NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS integer_encrypted (
     id SERIAL PRIMARY KEY,
     value eql_v2_encrypted NOT NULL
@@ -33,6 +56,26 @@ CREATE TABLE IF NOT EXISTS string_plaintext (
     value TEXT NOT NULL
 );
 
+CREATE TABLE IF NOT EXISTS string_plaintext_10000 (
+    id SERIAL PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS string_plaintext_100000 (
+    id SERIAL PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS string_plaintext_1000000 (
+    id SERIAL PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS string_plaintext_10000000 (
+    id SERIAL PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS string_encrypted (
     id SERIAL PRIMARY KEY,
     value eql_v2_encrypted NOT NULL
@@ -76,4 +119,84 @@ CREATE TABLE IF NOT EXISTS json_large_plaintext (
 CREATE TABLE IF NOT EXISTS json_large_encrypted (
     id SERIAL PRIMARY KEY,
     value eql_v2_encrypted NOT NULL
-);
\ No newline at end of file
+);
+
+CREATE TABLE IF NOT EXISTS json_small_plaintext_10000 (
+    id SERIAL PRIMARY KEY,
+    value JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_small_plaintext_100000 (
+    id SERIAL PRIMARY KEY,
+    value JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_small_plaintext_1000000 (
+    id SERIAL PRIMARY KEY,
+    value JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_small_plaintext_10000000 (
+    id SERIAL PRIMARY KEY,
+    value JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_small_encrypted_10000 (
+    id SERIAL PRIMARY KEY,
+    value eql_v2_encrypted NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_small_encrypted_100000 (
+    id SERIAL PRIMARY KEY,
+    value eql_v2_encrypted NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_small_encrypted_1000000 (
+    id SERIAL PRIMARY KEY,
+    value eql_v2_encrypted NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_small_encrypted_10000000 (
+    id SERIAL PRIMARY KEY,
+    value eql_v2_encrypted NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_large_plaintext_10000 (
+    id SERIAL PRIMARY KEY,
+    value JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_large_plaintext_100000 (
+    id SERIAL PRIMARY KEY,
+    value JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_large_plaintext_1000000 (
+    id SERIAL PRIMARY KEY,
+    value JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_large_plaintext_10000000 (
+    id SERIAL PRIMARY KEY,
+    value JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_large_encrypted_10000 (
+    id SERIAL PRIMARY KEY,
+    value eql_v2_encrypted NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_large_encrypted_100000 (
+    id SERIAL PRIMARY KEY,
+    value eql_v2_encrypted NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_large_encrypted_1000000 (
+    id SERIAL PRIMARY KEY,
+    value eql_v2_encrypted NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS json_large_encrypted_10000000 (
+    id SERIAL PRIMARY KEY,
+    value eql_v2_encrypted NOT NULL
+);
@@ -0,0 +1,46 @@
+//! Plaintext baselines for encrypted queries
+//!
+//! A scenario can give the plaintext equivalent of its query as `baseline`,
+//! which is run on the plaintext table of the same size with the scenario's
+//! parameters bound as they are. The query bench times it alongside the
+//! encrypted query as `{name}_plaintext`, so the report can show how much
+//! slower encryption makes each query.
+
+use crate::{param::positional_statement, Param};
+use anyhow::{Context, Result};
+use sqlx::{postgres::PgArguments, PgPool};
+
+/// A query on a plaintext table with its parameters.
+#[derive(Debug, Clone)]
+pub struct PlaintextQuery {
+    pub statement: String,
+    pub params: Vec<Param>,
+}
+
+impl PlaintextQuery {
+    /// `statement` can refer to `params` by position or, with `names`, as `:name`.
+    pub fn new(statement: &str, names: &[Option<&str>], params: Vec<Param>) -> Result<Self> {
+        Ok(Self {
+            statement: positional_statement(statement, names)?,
+            params,
+        })
+    }
+
+    pub fn arguments(&self) -> Result<PgArguments> {
+        let mut args = PgArguments::default();
+        for param in &self.params {
+            param.add_to(&mut args)?;
+        }
+        Ok(args)
+    }
+
+    /// Run the query and fetch every row, returning the number of rows.
+    pub async fn execute(&self, pool: &PgPool) -> Result<usize> {
+        let rows = sqlx::query_with(&self.statement, self.arguments()?)
+            .fetch_all(pool)
+            .await
+            .with_context(|| format!("failed to run {}", self.statement))?;
+
+        Ok(rows.len())
+    }
+}
@@ -13,6 +13,14 @@
 //!
 //! Example:
 //!   combine_benchmark encrypt_int
+//!   combine_benchmark encrypt_int_copy_binary
+//!
+//! Each run also records the time spent in each ingest phase (generate,
+//! encrypt, serialize and insert), summed over its batches. These are averaged
+//! across runs into `phases`.
+//!
+//! Ingest runs that load with COPY write their results under
+//! `{bench}_{load_method}` so they can be combined and compared separately.
 //!
 //! This reads:
 //! - target/{benchmark_name}_hyperfine.json (hyperfine results)
@@ -45,11 +53,65 @@ struct BenchmarkResult {
 #[derive(Debug, Deserialize)]
 struct OutputFile {
     inserted: u64,
+    #[serde(default = "default_ingest_mode")]
+    ingest_mode: String,
+    #[serde(default)]
+    encrypt_concurrency: Option<usize>,
+    #[serde(default)]
+    insert_concurrency: Option<usize>,
+    #[serde(default = "default_load_method")]
+    load_method: String,
+    #[serde(default)]
+    seed: Option<u64>,
+    #[serde(default)]
+    phases: Option<Phases>,
+}
+
+/// Seconds spent in each ingest phase, summed over the batches of a run.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+struct Phases {
+    generate_seconds: f64,
+    encrypt_seconds: f64,
+    serialize_seconds: f64,
+    insert_seconds: f64,
+}
+
+impl Phases {
+    /// Average the phases of the runs that recorded them.
+    fn mean<'a>(runs: impl Iterator<Item = &'a Phases>) -> Option<Self> {
+        let (count, total) = runs.fold((0, Phases::default()), |(count, mut total), p| {
+            total.generate_seconds += p.generate_seconds;
+            total.encrypt_seconds += p.encrypt_seconds;
+            total.serialize_seconds += p.serialize_seconds;
+            total.insert_seconds += p.insert_seconds;
+            (count + 1, total)
+        });
+        (count > 0).then(|| Phases {
+            generate_seconds: total.generate_seconds / count as f64,
+            encrypt_seconds: total.encrypt_seconds / count as f64,
+            serialize_seconds: total.serialize_seconds / count as f64,
+            insert_seconds: total.insert_seconds / count as f64,
+        })
+    }
+}
+
+fn default_ingest_mode() -> String {
+    "sequential".to_string()
+}
+
+fn default_load_method() -> String {
+    "insert".to_string()
 }
 
 #[derive(Debug, Serialize)]
 struct CombinedResult {
     num_records: u64,
+    ingest_mode: String,
+    encrypt_concurrency: Option<usize>,
+    insert_concurrency: Option<usize>,
+    load_method: String,
+    seeds: Vec<u64>,
+    phases: Option<Phases>,
     total_time_seconds: f64,
     total_records: u64,
     throughput_records_per_second: f64,
@@ -167,7 +229,7 @@ fn main() -> Result<()> {
     if args.len() != 2 {
         anyhow::bail!(
             "Usage: {} <benchmark_name>",
-            args.get(0)
+            args.first()
                 .map(|s| s.as_str())
                 .unwrap_or("combine_benchmark")
         );
@@ -195,6 +257,7 @@ fn main() -> Result<()> {
             .context("Failed to parse num_records as u64")?;
 
         let num_runs = result.times.len();
+        let mut outputs = Vec::with_capacity(num_runs);
 
         // Validate output files exist and contain correct data
         for run_idx in 0..num_runs {
@@ -217,6 +280,19 @@ fn main() -> Result<()> {
                     output.inserted
                 );
             }
+
+            outputs.push(output);
+        }
+
+        let first = outputs.first().context("No runs recorded")?;
+        if outputs
+            .iter()
+            .any(|o| o.ingest_mode != first.ingest_mode || o.load_method != first.load_method)
+        {
+            bail!(
+                "Runs for {} records used different ingest modes or load methods",
+                num_records
+            );
         }
 
         // Calculate average memory usage
@@ -231,6 +307,12 @@ fn main() -> Result<()> {
 
         combined_results.push(CombinedResult {
             num_records,
+            ingest_mode: first.ingest_mode.clone(),
+            encrypt_concurrency: first.encrypt_concurrency,
+            insert_concurrency: first.insert_concurrency,
+            load_method: first.load_method.clone(),
+            seeds: outputs.iter().filter_map(|o| o.seed).collect(),
+            phases: Phases::mean(outputs.iter().filter_map(|o| o.phases.as_ref())),
             total_time_seconds: total_time,
             total_records: num_records,
             throughput_records_per_second: throughput,
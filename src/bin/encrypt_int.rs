@@ -13,7 +13,15 @@
 //! Environment variables:
 //! - DATABASE_URL: PostgreSQL connection string
 //! - NUM_RECORDS: Number of records to generate (default: 10000)
+//! - INGEST_MODE: `sequential` (default) or `pipelined`
+//! - ENCRYPT_CONCURRENCY: Encryption workers in pipelined mode (default: 4)
+//! - INSERT_CONCURRENCY: Insert workers in pipelined mode (default: 4)
+//! - LOAD_METHOD: `insert` (default), `copy_text` or `copy_binary`
+//! - CHECKPOINT_ID: Optional ID to make the run resumable after an interruption
+//! - SEED: Seed for the generated data, for reproducible datasets (default: random)
+//! - WRITE_PLAINTEXT: `true` to also write each value to integer_plaintext (default: false)
 //! - TABLE_SUFFIX: Optional suffix for table name (e.g., _10000)
+//! - CS_CIPHER_BACKEND: `zerokms` (default) or `local` to run offline with fixed test keys
 //! - CS_CLIENT_ID: CipherStash client ID
 //! - CS_CLIENT_KEY: CipherStash client key  
 //! - CS_WORKSPACE_CRN: CipherStash workspace CRN
@@ -36,16 +44,19 @@ async fn main() -> Result<()> {
 
     let table_suffix = env::var("TABLE_SUFFIX").unwrap_or_default();
     let table_name = format!("integer_encrypted{}", table_suffix);
+    let plaintext_table_name = format!("integer_plaintext{}", table_suffix);
 
     IngestOptionsBuilder::new("encrypt_int")
         .num_records(num_records)
         .batch_size(1000)
         .identifier(Identifier::new(&table_name, "value"))
+        .plaintext_identifier(Identifier::new(&plaintext_table_name, "value"))
         .column_config(
             ColumnConfig::build("value")
                 .casts_as(ColumnType::Int)
                 .add_index(Index::new_ore()),
         )
+        .env_overrides()?
         .build()?
         .ingest::<i32, _>(Faker)
         .await?;
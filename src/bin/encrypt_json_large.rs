@@ -3,7 +3,7 @@
 //! This binary generates large, complex JSON objects containing user information, company
 //! details, addresses, and order history using the fake crate. The objects are encrypted
 //! using the cipherstash-client library with SteVec indexing and stored in the
-//! json_large_encrypted table.
+//! json_large_encrypted table (or a suffixed variant based on TABLE_SUFFIX).
 //!
 //! The encrypted JSON objects support:
 //! - Searchable encrypted vectors (SteVec) for term-based searches
@@ -11,6 +11,15 @@
 //! Environment variables:
 //! - DATABASE_URL: PostgreSQL connection string
 //! - NUM_RECORDS: Number of records to generate (default: 10000)
+//! - INGEST_MODE: `sequential` (default) or `pipelined`
+//! - ENCRYPT_CONCURRENCY: Encryption workers in pipelined mode (default: 4)
+//! - INSERT_CONCURRENCY: Insert workers in pipelined mode (default: 4)
+//! - LOAD_METHOD: `insert` (default), `copy_text` or `copy_binary`
+//! - CHECKPOINT_ID: Optional ID to make the run resumable after an interruption
+//! - SEED: Seed for the generated data, for reproducible datasets (default: random)
+//! - WRITE_PLAINTEXT: `true` to also write each value to json_large_plaintext (default: false)
+//! - TABLE_SUFFIX: Optional suffix for table name (e.g., _10000)
+//! - CS_CIPHER_BACKEND: `zerokms` (default) or `local` to run offline with fixed test keys
 //! - CS_CLIENT_ID: CipherStash client ID
 //! - CS_CLIENT_KEY: CipherStash client key  
 //! - CS_WORKSPACE_CRN: CipherStash workspace CRN
@@ -23,114 +32,9 @@ use cipherstash_client::{
         ColumnConfig, ColumnType,
     },
 };
-use dbbenches::{IngestOptionsBuilder, WrappedJson};
-use fake::{
-    faker::{address, chrono, company, internet, name, phone_number},
-    Dummy, Fake, Rng,
-};
-use serde_json::json;
+use dbbenches::{FakeJsonLarge, IngestOptionsBuilder, WrappedJson};
 use std::env;
 
-struct FakeJsonLarge;
-
-// FIXME: cipherstash-client doesn't have a From<serde_json::Value> for Plaintext impl yet, so we use String here
-impl Dummy<FakeJsonLarge> for WrappedJson {
-    fn dummy_with_rng<R: Rng + ?Sized>(_config: &FakeJsonLarge, _: &mut R) -> Self {
-        let department = [
-            "Engineering",
-            "Sales",
-            "Marketing",
-            "HR",
-            "Finance",
-            "Operations",
-        ]
-        .iter()
-        .take((1..6).fake())
-        .last()
-        .unwrap()
-        .to_string();
-        let type_ = ["Home", "Work", "Billing", "Shipping"]
-            .iter()
-            .take((1..4).fake())
-            .last()
-            .unwrap()
-            .to_string();
-        let status = [
-            "Pending",
-            "Processing",
-            "Shipped",
-            "Delivered",
-            "Cancelled",
-            "Returned",
-        ]
-        .iter()
-        .take((1..6).fake())
-        .last()
-        .unwrap()
-        .to_string();
-        let relationship = ["Spouse", "Parent", "Sibling", "Friend", "Other"]
-            .iter()
-            .take((1..5).fake())
-            .last()
-            .unwrap()
-            .to_string();
-
-        let value = json!({
-            "user": {
-                "first_name": name::en::FirstName().fake::<String>(),
-                "last_name": name::en::LastName().fake::<String>(),
-                "age": (18..=99).fake::<i32>(),
-                "email": internet::en::FreeEmail().fake::<String>(),
-                "username": internet::en::Username().fake::<String>(),
-                "contact": {
-                    "phone": phone_number::en::PhoneNumber().fake::<String>(),
-                    "mobile": phone_number::en::CellNumber().fake::<String>(),
-                    "emergency_contact": {
-                        "name": name::en::Name().fake::<String>(),
-                        "phone": phone_number::en::PhoneNumber().fake::<String>(),
-                        "relationship": relationship
-                    }
-                }
-            },
-            "company": {
-                "name": company::en::CompanyName().fake::<String>(),
-                "industry": company::en::Industry().fake::<String>(),
-                "position": company::en::Profession().fake::<String>(),
-                "department": department,
-                "salary": (40000..=300000).fake::<i32>(),
-                "start_date": chrono::en::Date().fake::<String>()
-            },
-            "addresses": (0..(1..4).fake::<i32>()).map(|_| {
-                json!({
-                    "type": type_,
-                    "street": address::en::StreetName().fake::<String>(),
-                    "city": address::en::CityName().fake::<String>(),
-                    "state": address::en::StateName().fake::<String>(),
-                    "zip": address::en::ZipCode().fake::<String>(),
-                    "country": "United States"
-                })
-            }).collect::<Vec<_>>(),
-            "orders": (0..(5..=20).fake::<i32>()).map(|_| {
-                json!({
-                    "order_id": format!("ORD-{}", (100000..=999999).fake::<i32>()),
-                    "date": chrono::en::Date().fake::<String>(),
-                    "total": (10.0..=5000.0).fake::<f64>(),
-                    "status": status,
-                    "items": (0..(1..=8).fake::<i32>()).map(|_| {
-                        json!({
-                            "product": company::en::Buzzword().fake::<String>(),
-                            "quantity": (1..=10).fake::<i32>(),
-                            "price": (5.0..=500.0).fake::<f64>()
-                        })
-                    }).collect::<Vec<_>>()
-                })
-            }).collect::<Vec<_>>()
-        });
-
-        WrappedJson(value)
-    }
-}
-
 #[tokio::main]
 async fn main() -> Result<()> {
     let num_records: i32 = env::var("NUM_RECORDS")
@@ -138,10 +42,15 @@ async fn main() -> Result<()> {
         .parse()
         .expect("NUM_RECORDS must be a valid integer");
 
+    let table_suffix = env::var("TABLE_SUFFIX").unwrap_or_default();
+    let table_name = format!("json_large_encrypted{}", table_suffix);
+    let plaintext_table_name = format!("json_large_plaintext{}", table_suffix);
+
     IngestOptionsBuilder::new("encrypt_json_large")
         .num_records(num_records)
         .batch_size(1000)
-        .identifier(Identifier::new("json_large_encrypted", "value"))
+        .identifier(Identifier::new(&table_name, "value"))
+        .plaintext_identifier(Identifier::new(&plaintext_table_name, "value"))
         .column_config(
             ColumnConfig::build("value")
                 .casts_as(ColumnType::JsonB)
@@ -151,6 +60,7 @@ async fn main() -> Result<()> {
                     term_filters: Default::default(),
                 })),
         )
+        .env_overrides()?
         .build()?
         .ingest::<WrappedJson, _>(FakeJsonLarge)
         .await?;
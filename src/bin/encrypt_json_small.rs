@@ -2,7 +2,8 @@
 //!
 //! This binary generates small JSON objects (first_name, last_name, age, email) using
 //! the fake crate and encrypts them using the cipherstash-client library with SteVec
-//! indexing, storing the encrypted values in the json_small_encrypted table.
+//! indexing, storing the encrypted values in the json_small_encrypted table (or a
+//! suffixed variant based on TABLE_SUFFIX).
 //!
 //! The encrypted JSON objects support:
 //! - Searchable encrypted vectors (SteVec) for term-based searches
@@ -10,6 +11,15 @@
 //! Environment variables:
 //! - DATABASE_URL: PostgreSQL connection string
 //! - NUM_RECORDS: Number of records to generate (default: 10000)
+//! - INGEST_MODE: `sequential` (default) or `pipelined`
+//! - ENCRYPT_CONCURRENCY: Encryption workers in pipelined mode (default: 4)
+//! - INSERT_CONCURRENCY: Insert workers in pipelined mode (default: 4)
+//! - LOAD_METHOD: `insert` (default), `copy_text` or `copy_binary`
+//! - CHECKPOINT_ID: Optional ID to make the run resumable after an interruption
+//! - SEED: Seed for the generated data, for reproducible datasets (default: random)
+//! - WRITE_PLAINTEXT: `true` to also write each value to json_small_plaintext (default: false)
+//! - TABLE_SUFFIX: Optional suffix for table name (e.g., _10000)
+//! - CS_CIPHER_BACKEND: `zerokms` (default) or `local` to run offline with fixed test keys
 //! - CS_CLIENT_ID: CipherStash client ID
 //! - CS_CLIENT_KEY: CipherStash client key  
 //! - CS_WORKSPACE_CRN: CipherStash workspace CRN
@@ -22,29 +32,9 @@ use cipherstash_client::{
         ColumnConfig, ColumnType,
     },
 };
-use dbbenches::{IngestOptionsBuilder, WrappedJson};
-use fake::{
-    faker::{internet, name},
-    Dummy, Fake, Rng,
-};
-use serde_json::json;
+use dbbenches::{FakeJsonSmall, IngestOptionsBuilder, WrappedJson};
 use std::env;
 
-struct FakeJsonSmall;
-
-// FIXME: cipherstash-client doesn't have a From<serde_json::Value> for Plaintext impl yet, so we use String here
-impl Dummy<FakeJsonSmall> for WrappedJson {
-    fn dummy_with_rng<R: Rng + ?Sized>(_config: &FakeJsonSmall, _: &mut R) -> Self {
-        let value = json!({
-            "first_name": name::en::FirstName().fake::<String>(),
-            "last_name": name::en::LastName().fake::<String>(),
-            "age": (18..=99).fake::<i32>(),
-            "email": internet::en::FreeEmail().fake::<String>(),
-        });
-        WrappedJson(value)
-    }
-}
-
 #[tokio::main]
 async fn main() -> Result<()> {
     let num_records: i32 = env::var("NUM_RECORDS")
@@ -52,10 +42,15 @@ async fn main() -> Result<()> {
         .parse()
         .expect("NUM_RECORDS must be a valid integer");
 
+    let table_suffix = env::var("TABLE_SUFFIX").unwrap_or_default();
+    let table_name = format!("json_small_encrypted{}", table_suffix);
+    let plaintext_table_name = format!("json_small_plaintext{}", table_suffix);
+
     IngestOptionsBuilder::new("encrypt_json_small")
         .num_records(num_records)
         .batch_size(1000)
-        .identifier(Identifier::new("json_small_encrypted", "value"))
+        .identifier(Identifier::new(&table_name, "value"))
+        .plaintext_identifier(Identifier::new(&plaintext_table_name, "value"))
         .column_config(
             ColumnConfig::build("value")
                 .casts_as(ColumnType::JsonB)
@@ -65,6 +60,7 @@ async fn main() -> Result<()> {
                     term_filters: Default::default(),
                 })),
         )
+        .env_overrides()?
         .build()?
         .ingest::<WrappedJson, _>(FakeJsonSmall)
         .await?;
@@ -0,0 +1,121 @@
+//! Concurrent load test with a weighted mix of query scenarios
+//!
+//! The criterion benches time one query at a time, so they give the latency of
+//! each query on an idle server. This binary runs many clients at once for a
+//! fixed time, each picking scenarios from the EXACT, MATCH and ORE scenario
+//! files by weight, and reports the throughput and p50/p95/p99/p99.9 latency of
+//! each scenario. Given several client counts it runs each in turn, to show how
+//! throughput and tail latency change with concurrency.
+//!
+//! Usage:
+//!   load_test
+//!
+//! Environment variables:
+//! - DATABASE_URL: PostgreSQL connection string
+//! - TARGET_ROWS: row count of the tables to query (default: 10000)
+//! - CLIENTS: comma-separated numbers of concurrent clients, each at least 1 (default: 16)
+//! - DURATION_SECS: time each client count is measured for (default: 30)
+//! - WARMUP_SECS: time run before measuring (default: 5)
+//! - SCENARIO: comma-separated scenario files, by name or path (default: exact,match,ore)
+//! - MIX: comma-separated `name=weight` pairs, where the name is a scenario file
+//!   (`ore`) or a scenario in it (`ore/range_gt_10`). Unlisted scenarios get 1
+//!   and 0 leaves a scenario out (default: every scenario weighted equally)
+//! - DECRYPT: `true` to decrypt the results of each query (default: false)
+//! - PARAM_POOL_SIZE: queries per scenario with a parameter distribution (default: the scenario's `pool`, or 32)
+//! - POOL_SEED: seed for drawing parameters and picking scenarios (default: 0)
+//! - CS_CIPHER_BACKEND: `zerokms` (default) or `local` to run offline with fixed test keys
+//!
+//! Outputs to:
+//! - results/load/load_test_clients_{c}_rows_{n}.json
+//!   (`load_test_decrypt_...` with DECRYPT=true)
+
+use anyhow::{bail, Context, Result};
+use dbbenches::{
+    env_list_or, env_or, init_scoped_cipher, run_load_test, LoadTestOptions, LoadTestReport,
+    PoolOptions, QueryMix, DEFAULT_WORKLOAD_SCENARIOS,
+};
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_CLIENTS: usize = 16;
+const DEFAULT_DURATION_SECS: u64 = 30;
+const DEFAULT_WARMUP_SECS: u64 = 5;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let database_url =
+        env::var("DATABASE_URL").context("DATABASE_URL environment variable must be set")?;
+    let target_rows = env::var("TARGET_ROWS").unwrap_or_else(|_| "10000".to_string());
+    let clients: Vec<usize> = env_list_or("CLIENTS", &[DEFAULT_CLIENTS])?;
+    if clients.contains(&0) {
+        bail!("CLIENTS must be at least 1 (got {clients:?})");
+    }
+    let duration = Duration::from_secs(env_or("DURATION_SECS", DEFAULT_DURATION_SECS)?);
+    let warmup = Duration::from_secs(env_or("WARMUP_SECS", DEFAULT_WARMUP_SECS)?);
+    let scenarios: Vec<String> = match env::var("SCENARIO") {
+        Ok(scenarios) => scenarios.split(',').map(|s| s.trim().to_string()).collect(),
+        Err(_) => DEFAULT_WORKLOAD_SCENARIOS
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+    };
+    let weights = env::var("MIX").unwrap_or_default();
+    let decrypt = env::var("DECRYPT").is_ok_and(|v| v == "true" || v == "1");
+    let pool_options = PoolOptions::from_env()?;
+
+    let max_clients = clients.iter().copied().max().unwrap_or(DEFAULT_CLIENTS);
+    let pool = PgPoolOptions::new()
+        .max_connections(max_clients as u32)
+        .connect(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    let cipher = init_scoped_cipher().await?;
+
+    println!(
+        "Building queries for {} at {target_rows} rows...",
+        scenarios.join(", ")
+    );
+    let mix = Arc::new(
+        QueryMix::build(
+            &scenarios,
+            &weights,
+            &pool,
+            &target_rows,
+            cipher,
+            &pool_options,
+        )
+        .await?,
+    );
+    for entry in &mix.entries {
+        println!(
+            "  {} (weight {}, {} queries)",
+            entry.name,
+            entry.weight,
+            entry.pool.len()
+        );
+    }
+
+    for clients in clients {
+        println!(
+            "Running {clients} clients for {}s after a {}s warmup...",
+            duration.as_secs(),
+            warmup.as_secs()
+        );
+        let options = LoadTestOptions {
+            clients,
+            warmup,
+            duration,
+            decrypt,
+            seed: pool_options.seed,
+        };
+        let scenarios = run_load_test(Arc::clone(&mix), &pool, &options).await?;
+        let report = LoadTestReport::new(&target_rows, &options, scenarios);
+        let path = report.write()?;
+        println!("Results written to {}", path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,136 @@
+//! Mixed read/write workload on an encrypted table
+//!
+//! Runs concurrent clients that interleave encrypted point lookups, range
+//! scans, updates and inserts at the given ratios against
+//! `integer_encrypted_{n}` or `string_encrypted_{n}`, and reports the
+//! throughput and latency percentiles of each operation. Writes maintain every
+//! index on the column while reads use them, so this shows what the query and
+//! ingest benchmarks can't: how the ORE, hash and GIN indexes behave under
+//! concurrent writes. Given several client counts it runs each in turn.
+//!
+//! Usage:
+//!   mixed_workload
+//!
+//! Environment variables:
+//! - DATABASE_URL: PostgreSQL connection string
+//! - TARGET_ROWS: row count of the table (default: 10000)
+//! - TABLE: `integer` (default) or `string`
+//! - RATIOS: comma-separated `operation=ratio` pairs for `read`, `scan`,
+//!   `update` and `insert`; unlisted operations aren't run
+//!   (default: read=50,scan=10,update=30,insert=10)
+//! - CLIENTS: comma-separated numbers of concurrent clients, each at least 1 (default: 16)
+//! - DURATION_SECS: time each client count is measured for (default: 30)
+//! - WARMUP_SECS: time run before measuring (default: 5)
+//! - SCAN_LENGTH: most rows a scan returns (default: 100)
+//! - KEY_SAMPLE: values sampled from the table for lookups and scans, at least 1 (default: 1000)
+//! - DECRYPT: `true` to decrypt the rows lookups and scans return (default: false)
+//! - WRITE_PLAINTEXT: `false` to leave the plaintext table as it is (default: true);
+//!   writes that can't be copied to it are reported as mirror errors
+//! - KEEP_INSERTS: `true` to keep the inserted rows (default: false, they're
+//!   deleted after each client count so the table stays at its target size)
+//!
+//! Updated rows are written back to their original values after each client
+//! count, whatever KEEP_INSERTS is, so later benchmarks see the seeded table.
+//! - POOL_SEED: seed for sampling values, picking operations and generating values (default: 0)
+//! - CS_CIPHER_BACKEND: `zerokms` (default) or `local` to run offline with fixed test keys
+//!
+//! Outputs to:
+//! - results/load/mixed_{table}_{mix}_clients_{c}_rows_{n}.json, where `mix`
+//!   is the ratios, such as `read50_scan10_update30_insert10`
+//!   (`mixed_{table}_decrypt_...` with DECRYPT=true)
+
+use anyhow::{bail, Context, Result};
+use dbbenches::{
+    env_list_or, env_or, init_scoped_cipher, run_mixed_workload, MixedOptions, MixedReport,
+    MixedTable, MixedWorkload, OperationMix, PoolOptions, DEFAULT_OPERATION_RATIOS,
+};
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_CLIENTS: usize = 16;
+const DEFAULT_DURATION_SECS: u64 = 30;
+const DEFAULT_WARMUP_SECS: u64 = 5;
+const DEFAULT_SCAN_LENGTH: usize = 100;
+const DEFAULT_KEY_SAMPLE: usize = 1000;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let database_url =
+        env::var("DATABASE_URL").context("DATABASE_URL environment variable must be set")?;
+    let target_rows = env::var("TARGET_ROWS").unwrap_or_else(|_| "10000".to_string());
+    let table: MixedTable = match env::var("TABLE") {
+        Ok(table) => table.parse()?,
+        Err(_) => MixedTable::Integer,
+    };
+    let mix = OperationMix::parse(
+        &env::var("RATIOS").unwrap_or_else(|_| DEFAULT_OPERATION_RATIOS.to_string()),
+    )?;
+    let clients: Vec<usize> = env_list_or("CLIENTS", &[DEFAULT_CLIENTS])?;
+    if clients.contains(&0) {
+        bail!("CLIENTS must be at least 1 (got {clients:?})");
+    }
+    let duration = Duration::from_secs(env_or("DURATION_SECS", DEFAULT_DURATION_SECS)?);
+    let warmup = Duration::from_secs(env_or("WARMUP_SECS", DEFAULT_WARMUP_SECS)?);
+    let scan_length = env_or("SCAN_LENGTH", DEFAULT_SCAN_LENGTH)?;
+    let key_sample = env_or("KEY_SAMPLE", DEFAULT_KEY_SAMPLE)?;
+    let decrypt = env::var("DECRYPT").is_ok_and(|v| v == "true" || v == "1");
+    let write_plaintext = !env::var("WRITE_PLAINTEXT").is_ok_and(|v| v == "false" || v == "0");
+    let keep_inserts = env::var("KEEP_INSERTS").is_ok_and(|v| v == "true" || v == "1");
+    let seed = PoolOptions::from_env()?.seed;
+
+    let max_clients = clients.iter().copied().max().unwrap_or(DEFAULT_CLIENTS);
+    let pool = PgPoolOptions::new()
+        .max_connections(max_clients as u32)
+        .connect(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    let cipher = init_scoped_cipher().await?;
+
+    println!(
+        "Sampling {key_sample} values from {}...",
+        table.table_name(&target_rows)
+    );
+    let workload =
+        Arc::new(MixedWorkload::load(table, &target_rows, &pool, cipher, key_sample, seed).await?);
+    println!(
+        "  {} values, ids {}..={}",
+        workload.keys.len(),
+        workload.ids.start(),
+        workload.ids.end()
+    );
+
+    for clients in clients {
+        println!(
+            "Running {mix} with {clients} clients for {}s after a {}s warmup...",
+            duration.as_secs(),
+            warmup.as_secs()
+        );
+        let options = MixedOptions {
+            mix: mix.clone(),
+            clients,
+            warmup,
+            duration,
+            decrypt,
+            write_plaintext,
+            scan_length,
+            seed,
+        };
+        let operations = run_mixed_workload(Arc::clone(&workload), &pool, &options).await?;
+        let report = MixedReport::new(&workload, &target_rows, &options, operations);
+        let path = report.write()?;
+        println!("Results written to {}", path.display());
+
+        let restored = workload.restore_updated(&pool).await?;
+        println!("Restored {restored} updated rows");
+
+        if !keep_inserts {
+            let deleted = workload.delete_inserted(&pool, write_plaintext).await?;
+            println!("Deleted {deleted} inserted rows");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! UPDATE and DELETE benchmarks on an encrypted, indexed table
+//!
+//! Times updates of encrypted values in place (re-encrypted with
+//! `EqlOperation::Store`) and deletes by an encrypted equality or ORE range
+//! on `integer_encrypted_{n}` or `string_encrypted_{n}`. Updates are timed with
+//! no indexes on the column, with each index alone and with all of them, to
+//! show the maintenance cost of the ORE operator class, hash and GIN bloom
+//! filter indexes. Every operation runs in a transaction that is rolled back,
+//! so the table is unchanged afterwards and is vacuumed to clear the dead rows.
+//!
+//! Dropping an index locks the table, so don't run this alongside other benchmarks.
+//! If any operation fails, the run stops without writing results.
+//!
+//! Usage:
+//!   modify_bench
+//!
+//! Environment variables:
+//! - DATABASE_URL: PostgreSQL connection string
+//! - TARGET_ROWS: row count of the table (default: 10000)
+//! - TABLE: `integer` (default) or `string`
+//! - OPERATIONS: comma-separated `update`, `delete_eq` and `delete_range`
+//!   (default: every one the table supports; `delete_range` is integer only)
+//! - ITERATIONS: statements timed per operation and set of indexes (default: 200)
+//! - DELETE_RANGE_ROWS: rows each range delete aims to match (default: 10)
+//! - POOL_SEED: seed for sampling values, picking rows and generating values (default: 0)
+//! - CS_CIPHER_BACKEND: `zerokms` (default) or `local` to run offline with fixed test keys
+//!
+//! Outputs to:
+//! - results/modify/{table}_rows_{n}.json
+
+use anyhow::{bail, Context, Result};
+use dbbenches::{
+    env_or, init_scoped_cipher, run_modification, IndexSet, MixedTable, MixedWorkload,
+    Modification, ModifyOptions, ModifyReport, PoolOptions, TableIndex,
+};
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+
+const DEFAULT_ITERATIONS: usize = 200;
+const DEFAULT_DELETE_RANGE_ROWS: u32 = 10;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let database_url =
+        env::var("DATABASE_URL").context("DATABASE_URL environment variable must be set")?;
+    let target_rows = env::var("TARGET_ROWS").unwrap_or_else(|_| "10000".to_string());
+    let table: MixedTable = match env::var("TABLE") {
+        Ok(table) => table.parse()?,
+        Err(_) => MixedTable::Integer,
+    };
+    let operations: Vec<Modification> = match env::var("OPERATIONS") {
+        Ok(operations) => operations
+            .split(',')
+            .map(|s| s.trim().parse())
+            .collect::<Result<_>>()?,
+        Err(_) => Modification::ALL
+            .into_iter()
+            .filter(|op| op.supports(table))
+            .collect(),
+    };
+    if let Some(op) = operations.iter().find(|op| !op.supports(table)) {
+        bail!("{} isn't supported on the {table} table", op.as_str());
+    }
+    let options = ModifyOptions {
+        iterations: env_or("ITERATIONS", DEFAULT_ITERATIONS)?,
+        delete_range_rows: env_or("DELETE_RANGE_ROWS", DEFAULT_DELETE_RANGE_ROWS)?,
+        seed: PoolOptions::from_env()?.seed,
+    };
+
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    let cipher = init_scoped_cipher().await?;
+
+    let table_name = table.table_name(&target_rows);
+    println!(
+        "Sampling {} values from {table_name}...",
+        options.iterations
+    );
+    let workload = MixedWorkload::load(
+        table,
+        &target_rows,
+        &pool,
+        cipher,
+        options.iterations,
+        options.seed,
+    )
+    .await?;
+
+    let indexes = TableIndex::list(&pool, &table_name).await?;
+    println!(
+        "  indexes: {}",
+        indexes
+            .iter()
+            .map(|i| format!("{} ({})", i.name, i.kind))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let sets = IndexSet::sets(&indexes);
+    let every_index = sets.last().expect("there is always at least one index set");
+
+    let mut results = Vec::new();
+    for op in operations {
+        let op_sets = match op.maintains_indexes() {
+            true => sets.as_slice(),
+            false => std::slice::from_ref(every_index),
+        };
+        for set in op_sets {
+            println!(
+                "Timing {} {} with indexes: {}...",
+                options.iterations,
+                op.as_str(),
+                set.name
+            );
+            let result = run_modification(&workload, &pool, op, set, &options)
+                .await
+                .with_context(|| format!("{} with indexes: {} failed", op.as_str(), set.name))?;
+            results.push(result);
+        }
+    }
+
+    println!("Vacuuming {table_name}...");
+    sqlx::query(&format!("VACUUM {table_name}"))
+        .execute(&pool)
+        .await?;
+
+    let report = ModifyReport::new(&workload, &target_rows, indexes, results);
+    let path = report.write()?;
+    println!("Results written to {}", path.display());
+
+    Ok(())
+}
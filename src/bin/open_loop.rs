@@ -0,0 +1,157 @@
+//! Open-loop load generator with a rate sweep
+//!
+//! Closed-loop benchmarks (criterion, `load_test`) start each query when the
+//! previous one returns, so when the database slows down they send fewer
+//! queries and the queueing delay never shows up in their latencies. This
+//! binary starts queries on a fixed schedule instead, at each target rate in
+//! turn, and measures each query from when it was scheduled to start. Running
+//! it over increasing rates shows where a workload saturates: throughput stops
+//! following the target rate and latency climbs steeply.
+//!
+//! Usage:
+//!   open_loop
+//!
+//! Environment variables:
+//! - DATABASE_URL: PostgreSQL connection string
+//! - TARGET_ROWS: row count of the tables to query (default: 10000)
+//! - RATES: comma-separated target rates in queries/sec (default: 10,25,50,100,250,500,1000)
+//! - ARRIVALS: `constant` (default) or `poisson` spacing between queries
+//! - DURATION_SECS: time each rate is measured for (default: 30)
+//! - WARMUP_SECS: time each rate is run before measuring (default: 5)
+//! - MAX_CONNECTIONS: size of the connection pool (default: 32)
+//! - MAX_IN_FLIGHT: most queries running or waiting for a connection at once, at least 1 (default: 10000)
+//! - SCENARIO: comma-separated scenario files, by name or path (default: exact,match,ore)
+//! - WORKLOAD: name for the results (default: the scenario names joined with `+`)
+//! - MIX: comma-separated `name=weight` pairs, as for `load_test`
+//! - DECRYPT: `true` to decrypt the results of each query (default: false)
+//! - PARAM_POOL_SIZE: queries per scenario with a parameter distribution (default: the scenario's `pool`, or 32)
+//! - POOL_SEED: seed for drawing parameters, arrivals and scenarios (default: 0)
+//! - CS_CIPHER_BACKEND: `zerokms` (default) or `local` to run offline with fixed test keys
+//!
+//! Outputs to:
+//! - results/load/open_loop_{workload}_rate_{r}_rows_{n}.json: throughput and
+//!   latency percentiles, overall and per scenario
+//! - results/load/open_loop_{workload}_rate_{r}_rows_{n}.hlog: the latency
+//!   histograms as an HdrHistogram interval log
+//!
+//! With DECRYPT=true the files are named `open_loop_decrypt_...`.
+
+use anyhow::{Context, Result};
+use dbbenches::{
+    env_list_or, env_or, init_scoped_cipher, run_open_loop, Arrivals, OpenLoopOptions,
+    OpenLoopReport, PoolOptions, QueryMix, DEFAULT_WORKLOAD_SCENARIOS,
+};
+use sqlx::postgres::PgPoolOptions;
+use std::env;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_RATES: &[f64] = &[10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+const DEFAULT_DURATION_SECS: u64 = 30;
+const DEFAULT_WARMUP_SECS: u64 = 5;
+const DEFAULT_MAX_CONNECTIONS: u32 = 32;
+const DEFAULT_MAX_IN_FLIGHT: usize = 10_000;
+
+/// A rate is sustained when the measured throughput is within 5% of it.
+const SUSTAINED_FRACTION: f64 = 0.95;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let database_url =
+        env::var("DATABASE_URL").context("DATABASE_URL environment variable must be set")?;
+    let target_rows = env::var("TARGET_ROWS").unwrap_or_else(|_| "10000".to_string());
+    let rates: Vec<f64> = env_list_or("RATES", DEFAULT_RATES)?;
+    let arrivals: Arrivals = match env::var("ARRIVALS") {
+        Ok(arrivals) => arrivals.parse()?,
+        Err(_) => Arrivals::default(),
+    };
+    let duration = Duration::from_secs(env_or("DURATION_SECS", DEFAULT_DURATION_SECS)?);
+    let warmup = Duration::from_secs(env_or("WARMUP_SECS", DEFAULT_WARMUP_SECS)?);
+    let max_connections = env_or("MAX_CONNECTIONS", DEFAULT_MAX_CONNECTIONS)?;
+    let max_in_flight = env_or("MAX_IN_FLIGHT", DEFAULT_MAX_IN_FLIGHT)?;
+    let scenarios: Vec<String> = match env::var("SCENARIO") {
+        Ok(scenarios) => scenarios.split(',').map(|s| s.trim().to_string()).collect(),
+        Err(_) => DEFAULT_WORKLOAD_SCENARIOS
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+    };
+    let workload = env::var("WORKLOAD").unwrap_or_else(|_| {
+        scenarios
+            .iter()
+            .map(|s| {
+                Path::new(s)
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+            })
+            .collect::<Vec<_>>()
+            .join("+")
+    });
+    let weights = env::var("MIX").unwrap_or_default();
+    let decrypt = env::var("DECRYPT").is_ok_and(|v| v == "true" || v == "1");
+    let pool_options = PoolOptions::from_env()?;
+
+    let pool = PgPoolOptions::new()
+        .max_connections(max_connections)
+        .connect(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    let cipher = init_scoped_cipher().await?;
+
+    println!("Building queries for {workload} at {target_rows} rows...");
+    let mix = Arc::new(
+        QueryMix::build(
+            &scenarios,
+            &weights,
+            &pool,
+            &target_rows,
+            cipher,
+            &pool_options,
+        )
+        .await?,
+    );
+    for entry in &mix.entries {
+        println!(
+            "  {} (weight {}, {} queries)",
+            entry.name,
+            entry.weight,
+            entry.pool.len()
+        );
+    }
+
+    let mut sustained = None;
+    for rate in rates {
+        println!(
+            "Running {rate} queries/sec ({arrivals}) for {}s after a {}s warmup...",
+            duration.as_secs(),
+            warmup.as_secs()
+        );
+        let options = OpenLoopOptions {
+            rate,
+            arrivals,
+            warmup,
+            duration,
+            decrypt,
+            seed: pool_options.seed,
+            max_in_flight,
+        };
+        let run = run_open_loop(Arc::clone(&mix), &pool, &options).await?;
+        let report = OpenLoopReport::new(&workload, &target_rows, &options, run);
+        let path = report.write()?;
+        println!("Results written to {}", path.display());
+
+        if report.qps >= rate * SUSTAINED_FRACTION {
+            sustained = Some(rate);
+        }
+    }
+
+    match sustained {
+        Some(rate) => println!("{workload}: highest sustained rate {rate} queries/sec"),
+        None => println!("{workload}: no rate was sustained"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,174 @@
+//! Record the storage footprint of the prepared tables
+//!
+//! Encrypted values carry their index terms (ORE blocks, bloom filters, HMACs
+//! and SteVec entries) alongside the ciphertext, so they take much more space
+//! than the plaintext they encrypt. This binary measures each prepared table
+//! so the cost can be compared with its plaintext counterpart:
+//! - total_bytes: `pg_total_relation_size` (heap, TOAST and indexes)
+//! - relation_bytes: `pg_relation_size` (the heap alone)
+//! - toast_bytes: the table's TOAST relation, including its index
+//! - indexes: the size of each index
+//! - avg_value_bytes: average stored size of `value` (`pg_column_size`, after compression)
+//! - avg_value_text_bytes: average length of `value` as text, as sent to and from clients
+//!
+//! Empty tables are skipped.
+//!
+//! Usage:
+//!   storage_report
+//!
+//! Environment variables:
+//! - DATABASE_URL: PostgreSQL connection string
+//! - TABLES: Optional comma-separated tables to measure (default: every
+//!   `*_encrypted*` and `*_plaintext*` table)
+//!
+//! Outputs to:
+//! - results/storage/{table}.json
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::{postgres::PgPoolOptions, FromRow, PgPool};
+use std::env;
+use std::fs;
+
+#[derive(Debug, Serialize, FromRow)]
+struct IndexSize {
+    name: String,
+    bytes: i64,
+}
+
+#[derive(Debug, Serialize)]
+struct TableStorage {
+    table: String,
+    rows: i64,
+    total_bytes: i64,
+    relation_bytes: i64,
+    toast_bytes: i64,
+    indexes_bytes: i64,
+    indexes: Vec<IndexSize>,
+    avg_value_bytes: f64,
+    avg_value_text_bytes: f64,
+}
+
+impl TableStorage {
+    async fn measure(pool: &PgPool, table: &str) -> Result<Self> {
+        let (total_bytes, relation_bytes, toast_bytes, indexes_bytes): (i64, i64, i64, i64) =
+            sqlx::query_as(
+                "SELECT pg_total_relation_size(c.oid),
+                        pg_relation_size(c.oid),
+                        COALESCE(pg_total_relation_size(NULLIF(c.reltoastrelid, 0)), 0),
+                        pg_indexes_size(c.oid)
+                 FROM pg_class c
+                 WHERE c.oid = $1::regclass",
+            )
+            .bind(table)
+            .fetch_one(pool)
+            .await
+            .with_context(|| format!("failed to read the size of {table}"))?;
+
+        let indexes: Vec<IndexSize> = sqlx::query_as(
+            "SELECT c.relname::text AS name, pg_relation_size(c.oid) AS bytes
+             FROM pg_index i
+             JOIN pg_class c ON c.oid = i.indexrelid
+             WHERE i.indrelid = $1::regclass
+             ORDER BY c.relname",
+        )
+        .bind(table)
+        .fetch_all(pool)
+        .await
+        .with_context(|| format!("failed to read the indexes of {table}"))?;
+
+        // Table names come from pg_tables or TABLES, so quote them as identifiers
+        let (rows, avg_value_bytes, avg_value_text_bytes): (i64, Option<f64>, Option<f64>) =
+            sqlx::query_as(&format!(
+                "SELECT count(*),
+                        avg(pg_column_size(value))::float8,
+                        avg(octet_length(value::text))::float8
+                 FROM \"{}\"",
+                table.replace('"', "\"\"")
+            ))
+            .fetch_one(pool)
+            .await
+            .with_context(|| format!("failed to read the values of {table}"))?;
+
+        Ok(Self {
+            table: table.to_string(),
+            rows,
+            total_bytes,
+            relation_bytes,
+            toast_bytes,
+            indexes_bytes,
+            indexes,
+            avg_value_bytes: avg_value_bytes.unwrap_or_default(),
+            avg_value_text_bytes: avg_value_text_bytes.unwrap_or_default(),
+        })
+    }
+
+    fn bytes_per_row(&self) -> f64 {
+        self.total_bytes as f64 / self.rows.max(1) as f64
+    }
+}
+
+async fn tables(pool: &PgPool) -> Result<Vec<String>> {
+    if let Ok(tables) = env::var("TABLES") {
+        return Ok(tables
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect());
+    }
+
+    let tables = sqlx::query_scalar(
+        "SELECT tablename::text FROM pg_tables
+         WHERE schemaname = 'public'
+           AND (tablename LIKE '%\\_encrypted%' OR tablename LIKE '%\\_plaintext%')
+         ORDER BY tablename",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tables)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let database_url =
+        env::var("DATABASE_URL").context("DATABASE_URL environment variable must be set")?;
+
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await
+        .context("Failed to connect to database")?;
+
+    fs::create_dir_all("results/storage")?;
+
+    for table in tables(&pool).await? {
+        let storage = TableStorage::measure(&pool, &table).await?;
+        if storage.rows == 0 {
+            println!("{table}: empty, skipping");
+            continue;
+        }
+
+        println!(
+            "{}: {} rows, {} total ({:.0} bytes/row), {} heap, {} TOAST, {} indexes, {:.0} bytes/value stored, {:.0} as text",
+            storage.table,
+            storage.rows,
+            storage.total_bytes,
+            storage.bytes_per_row(),
+            storage.relation_bytes,
+            storage.toast_bytes,
+            storage.indexes_bytes,
+            storage.avg_value_bytes,
+            storage.avg_value_text_bytes,
+        );
+        for index in &storage.indexes {
+            println!("    {}: {}", index.name, index.bytes);
+        }
+
+        let path = format!("results/storage/{table}.json");
+        fs::write(&path, serde_json::to_string_pretty(&storage)?)
+            .with_context(|| format!("failed to write {path}"))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,178 @@
+//! Checkpoints for resumable ingest runs
+//!
+//! A checkpoint is stored in Postgres next to the data it describes. Each batch
+//! is written in a transaction that also records the batch in
+//! `ingest_checkpoint_batches`, so a batch is either committed together with
+//! its checkpoint or not at all. When a run with the same checkpoint ID is
+//! restarted before it completes, it resumes with the original plan
+//! (`num_records`, `batch_size` and `seed`) and skips every batch already
+//! committed. Each record is generated from the seed and its position in the
+//! stream, so the resumed batches contain the same data they would have had.
+//! A completed checkpoint isn't started again, since that would write the
+//! whole dataset a second time.
+
+use anyhow::{bail, Result};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashSet;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS ingest_checkpoints (
+        checkpoint_id TEXT PRIMARY KEY,
+        num_records INT NOT NULL,
+        batch_size INT NOT NULL,
+        seed BIGINT NOT NULL,
+        completed BOOLEAN NOT NULL DEFAULT false,
+        started_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+    )",
+    "CREATE TABLE IF NOT EXISTS ingest_checkpoint_batches (
+        checkpoint_id TEXT NOT NULL REFERENCES ingest_checkpoints ON DELETE CASCADE,
+        batch_index INT NOT NULL,
+        seed_position INT NOT NULL,
+        rows_committed INT NOT NULL,
+        committed_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+        PRIMARY KEY (checkpoint_id, batch_index)
+    )",
+];
+
+/// A contiguous slice of the generated record stream, written as a unit.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Batch {
+    pub(crate) index: usize,
+    /// Position of the batch's first record in the generated stream.
+    pub(crate) seed_position: usize,
+    pub(crate) len: usize,
+}
+
+#[derive(Debug)]
+pub(crate) struct Checkpoint {
+    id: String,
+    pub(crate) num_records: i32,
+    pub(crate) batch_size: usize,
+    pub(crate) seed: u64,
+    committed: HashSet<usize>,
+    rows_committed: i64,
+}
+
+impl Checkpoint {
+    /// Resume the incomplete run recorded under `id`, or start a new one with the given plan.
+    ///
+    /// Fails if the run recorded under `id` has already completed.
+    pub(crate) async fn load_or_start(
+        pool: &PgPool,
+        id: &str,
+        num_records: i32,
+        batch_size: usize,
+        seed: u64,
+    ) -> Result<Self> {
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(pool).await?;
+        }
+
+        let existing: Option<(i32, i32, i64, bool)> = sqlx::query_as(
+            "SELECT num_records, batch_size, seed, completed FROM ingest_checkpoints
+             WHERE checkpoint_id = $1",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        if let Some((.., true)) = existing {
+            bail!("checkpoint {id:?} already completed; choose a new CHECKPOINT_ID or truncate");
+        }
+        if let Some((num_records, batch_size, seed, _)) = existing {
+            let batches: Vec<(i32, i32)> = sqlx::query_as(
+                "SELECT batch_index, rows_committed FROM ingest_checkpoint_batches
+                 WHERE checkpoint_id = $1",
+            )
+            .bind(id)
+            .fetch_all(pool)
+            .await?;
+
+            return Ok(Self {
+                id: id.to_string(),
+                num_records,
+                batch_size: batch_size as usize,
+                // Stored as BIGINT, so the bits round trip through i64
+                seed: seed as u64,
+                committed: batches.iter().map(|(index, _)| *index as usize).collect(),
+                rows_committed: batches.iter().map(|(_, rows)| *rows as i64).sum(),
+            });
+        }
+
+        let batch_size_i32 = i32::try_from(batch_size)?;
+        if batch_size_i32 == 0 {
+            bail!("batch_size must be at least 1");
+        }
+
+        sqlx::query(
+            "INSERT INTO ingest_checkpoints (checkpoint_id, num_records, batch_size, seed)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(id)
+        .bind(num_records)
+        .bind(batch_size_i32)
+        .bind(seed as i64)
+        .execute(pool)
+        .await?;
+
+        Ok(Self {
+            id: id.to_string(),
+            num_records,
+            batch_size,
+            seed,
+            committed: HashSet::new(),
+            rows_committed: 0,
+        })
+    }
+
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub(crate) fn is_resumed(&self) -> bool {
+        !self.committed.is_empty()
+    }
+
+    pub(crate) fn rows_committed(&self) -> i64 {
+        self.rows_committed
+    }
+
+    pub(crate) fn is_committed(&self, batch: &Batch) -> bool {
+        self.committed.contains(&batch.index)
+    }
+
+    /// Record a batch as committed, inside the transaction that writes it.
+    pub(crate) async fn record(&self, conn: &mut PgConnection, batch: &Batch) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO ingest_checkpoint_batches
+                (checkpoint_id, batch_index, seed_position, rows_committed)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(&self.id)
+        .bind(i32::try_from(batch.index)?)
+        .bind(i32::try_from(batch.seed_position)?)
+        .bind(i32::try_from(batch.len)?)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query("UPDATE ingest_checkpoints SET updated_at = now() WHERE checkpoint_id = $1")
+            .bind(&self.id)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn complete(&self, pool: &PgPool) -> Result<()> {
+        sqlx::query(
+            "UPDATE ingest_checkpoints SET completed = true, updated_at = now()
+             WHERE checkpoint_id = $1",
+        )
+        .bind(&self.id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
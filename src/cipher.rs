@@ -0,0 +1,137 @@
+//! Cipher backends used by the ingest binaries and query benchmarks
+//!
+//! The backend is selected with the `CS_CIPHER_BACKEND` environment variable:
+//! - `zerokms` (default): keys are issued by CipherStash ZeroKMS using the
+//!   `CS_CLIENT_ID`, `CS_CLIENT_KEY`, `CS_CLIENT_ACCESS_KEY` and
+//!   `CS_WORKSPACE_CRN` credentials
+//! - `local`: keys are issued by an in-process stand-in for ZeroKMS using fixed
+//!   test keys, so no credentials or network access are required
+//!
+//! Both backends produce a [`Cipher`], so the rest of the crate doesn't need to
+//! know which one is in use. Because the local test keys are fixed, data
+//! ingested with the local backend can be queried and decrypted by a later
+//! process that also uses the local backend, but not by ZeroKMS (and vice versa).
+
+use crate::local_kms;
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use cipherstash_client::{
+    config::EnvSource,
+    credentials::{
+        ClearTokenError, Credentials, GetTokenError, ServiceCredentials, ServiceToken,
+        StaticCredentials,
+    },
+    encryption::ScopedCipher,
+    zerokms::ClientKey,
+    ZeroKMS, ZeroKMSConfig,
+};
+use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// The cipher type shared by every backend.
+pub type Cipher = ScopedCipher<CipherCredentials>;
+
+/// Credentials for the selected [`CipherBackend`].
+pub enum CipherCredentials {
+    ZeroKms(ServiceCredentials),
+    Local(StaticCredentials<ServiceToken>),
+}
+
+#[async_trait]
+impl Credentials for CipherCredentials {
+    type Token = ServiceToken;
+
+    async fn get_token(&self) -> Result<Self::Token, GetTokenError> {
+        match self {
+            Self::ZeroKms(credentials) => credentials.get_token().await,
+            Self::Local(credentials) => credentials.get_token().await,
+        }
+    }
+
+    async fn clear_token(&self) -> Result<(), ClearTokenError> {
+        match self {
+            Self::ZeroKms(credentials) => credentials.clear_token().await,
+            Self::Local(credentials) => credentials.clear_token().await,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CipherBackend {
+    #[default]
+    ZeroKms,
+    Local,
+}
+
+impl CipherBackend {
+    pub const ENV_VAR: &'static str = "CS_CIPHER_BACKEND";
+
+    /// Read the backend from `CS_CIPHER_BACKEND`, defaulting to ZeroKMS.
+    pub fn from_env() -> Result<Self> {
+        match env::var(Self::ENV_VAR) {
+            Ok(value) => value.parse(),
+            Err(env::VarError::NotPresent) => Ok(Self::default()),
+            Err(err) => Err(err).context(format!("failed to read {}", Self::ENV_VAR)),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ZeroKms => "zerokms",
+            Self::Local => "local",
+        }
+    }
+
+    pub async fn init(self) -> Result<Arc<Cipher>> {
+        let client = match self {
+            Self::ZeroKms => {
+                let config = ZeroKMSConfig::builder()
+                    .add_source(EnvSource::new())
+                    .build_with_client_key()
+                    .context("failed to build config")?;
+
+                config.create_client_with_credentials(CipherCredentials::ZeroKms(
+                    config.credentials(),
+                ))
+            }
+            Self::Local => {
+                let base_url = local_kms::spawn().await?;
+                let client_key =
+                    ClientKey::from_hex_v1(local_kms::CLIENT_ID, local_kms::CLIENT_KEY_HEX)
+                        .context("failed to decode local client key")?;
+                let token = ServiceToken::new("local", u64::MAX);
+
+                ZeroKMS::new_with_client_key(
+                    &base_url,
+                    CipherCredentials::Local(StaticCredentials::new(token)),
+                    None,
+                    client_key,
+                )
+            }
+        };
+
+        let scoped_cipher = ScopedCipher::init_default(Arc::new(client)).await?;
+        Ok(Arc::new(scoped_cipher))
+    }
+}
+
+impl FromStr for CipherBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "zerokms" => Ok(Self::ZeroKms),
+            "local" => Ok(Self::Local),
+            other => bail!(
+                "{} must be one of: zerokms, local (got {other:?})",
+                Self::ENV_VAR
+            ),
+        }
+    }
+}
+
+/// Initialise a [`Cipher`] for the backend selected by `CS_CIPHER_BACKEND`.
+pub async fn init_scoped_cipher() -> Result<Arc<Cipher>> {
+    CipherBackend::from_env()?.init().await
+}
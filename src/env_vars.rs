@@ -0,0 +1,36 @@
+//! Parsing benchmark settings from environment variables
+
+use anyhow::{Context, Result};
+use std::env;
+use std::str::FromStr;
+
+/// Parse the environment variable `name`, or return `None` if it isn't set.
+pub fn env_opt<T: FromStr>(name: &str) -> Result<Option<T>> {
+    match env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .ok()
+            .map(Some)
+            .with_context(|| format!("{name} must be a valid number (got {value:?})")),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Parse the environment variable `name`, or return `default` if it isn't set.
+pub fn env_or<T: FromStr>(name: &str, default: T) -> Result<T> {
+    Ok(env_opt(name)?.unwrap_or(default))
+}
+
+/// Parse the comma-separated environment variable `name`, or return `default`
+/// if it isn't set.
+pub fn env_list_or<T: FromStr + Clone>(name: &str, default: &[T]) -> Result<Vec<T>> {
+    match env::var(name) {
+        Ok(values) => values
+            .split(',')
+            .map(|s| s.trim().parse().ok())
+            .collect::<Option<_>>()
+            .with_context(|| format!("{name} must be comma-separated numbers (got {values:?})")),
+        Err(_) => Ok(default.to_vec()),
+    }
+}
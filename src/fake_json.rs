@@ -0,0 +1,128 @@
+//! Fake JSON documents for the `json_small` and `json_large` tables
+//!
+//! Shared by the ingest binaries and the benches that need payloads of the
+//! same shape.
+
+use crate::WrappedJson;
+use fake::{
+    faker::{address, chrono, company, internet, name, phone_number},
+    Dummy, Fake, Rng,
+};
+use serde_json::json;
+
+/// A user with a name, age and email.
+pub struct FakeJsonSmall;
+
+// FIXME: cipherstash-client doesn't have a From<serde_json::Value> for Plaintext impl yet, so we use String here
+impl Dummy<FakeJsonSmall> for WrappedJson {
+    fn dummy_with_rng<R: Rng + ?Sized>(_config: &FakeJsonSmall, rng: &mut R) -> Self {
+        let value = json!({
+            "first_name": name::en::FirstName().fake_with_rng::<String, _>(rng),
+            "last_name": name::en::LastName().fake_with_rng::<String, _>(rng),
+            "age": (18..=99).fake_with_rng::<i32, _>(rng),
+            "email": internet::en::FreeEmail().fake_with_rng::<String, _>(rng),
+        });
+        WrappedJson(value)
+    }
+}
+
+/// A user with contact details, a company, addresses and up to 20 orders.
+pub struct FakeJsonLarge;
+
+// FIXME: cipherstash-client doesn't have a From<serde_json::Value> for Plaintext impl yet, so we use String here
+impl Dummy<FakeJsonLarge> for WrappedJson {
+    fn dummy_with_rng<R: Rng + ?Sized>(_config: &FakeJsonLarge, rng: &mut R) -> Self {
+        let department = [
+            "Engineering",
+            "Sales",
+            "Marketing",
+            "HR",
+            "Finance",
+            "Operations",
+        ]
+        .iter()
+        .take((1..6).fake_with_rng(rng))
+        .next_back()
+        .unwrap()
+        .to_string();
+        let type_ = ["Home", "Work", "Billing", "Shipping"]
+            .iter()
+            .take((1..4).fake_with_rng(rng))
+            .next_back()
+            .unwrap()
+            .to_string();
+        let status = [
+            "Pending",
+            "Processing",
+            "Shipped",
+            "Delivered",
+            "Cancelled",
+            "Returned",
+        ]
+        .iter()
+        .take((1..6).fake_with_rng(rng))
+        .next_back()
+        .unwrap()
+        .to_string();
+        let relationship = ["Spouse", "Parent", "Sibling", "Friend", "Other"]
+            .iter()
+            .take((1..5).fake_with_rng(rng))
+            .next_back()
+            .unwrap()
+            .to_string();
+
+        let value = json!({
+            "user": {
+                "first_name": name::en::FirstName().fake_with_rng::<String, _>(rng),
+                "last_name": name::en::LastName().fake_with_rng::<String, _>(rng),
+                "age": (18..=99).fake_with_rng::<i32, _>(rng),
+                "email": internet::en::FreeEmail().fake_with_rng::<String, _>(rng),
+                "username": internet::en::Username().fake_with_rng::<String, _>(rng),
+                "contact": {
+                    "phone": phone_number::en::PhoneNumber().fake_with_rng::<String, _>(rng),
+                    "mobile": phone_number::en::CellNumber().fake_with_rng::<String, _>(rng),
+                    "emergency_contact": {
+                        "name": name::en::Name().fake_with_rng::<String, _>(rng),
+                        "phone": phone_number::en::PhoneNumber().fake_with_rng::<String, _>(rng),
+                        "relationship": relationship
+                    }
+                }
+            },
+            "company": {
+                "name": company::en::CompanyName().fake_with_rng::<String, _>(rng),
+                "industry": company::en::Industry().fake_with_rng::<String, _>(rng),
+                "position": company::en::Profession().fake_with_rng::<String, _>(rng),
+                "department": department,
+                "salary": (40000..=300000).fake_with_rng::<i32, _>(rng),
+                "start_date": chrono::en::Date().fake_with_rng::<String, _>(rng)
+            },
+            "addresses": (0..(1..4).fake_with_rng::<i32, _>(rng)).map(|_| {
+                json!({
+                    "type": type_,
+                    "street": address::en::StreetName().fake_with_rng::<String, _>(rng),
+                    "city": address::en::CityName().fake_with_rng::<String, _>(rng),
+                    "state": address::en::StateName().fake_with_rng::<String, _>(rng),
+                    "zip": address::en::ZipCode().fake_with_rng::<String, _>(rng),
+                    "country": "United States"
+                })
+            }).collect::<Vec<_>>(),
+            "orders": (0..(5..=20).fake_with_rng::<i32, _>(rng)).map(|_| {
+                json!({
+                    "order_id": format!("ORD-{}", (100000..=999999).fake_with_rng::<i32, _>(rng)),
+                    "date": chrono::en::Date().fake_with_rng::<String, _>(rng),
+                    "total": (10.0..=5000.0).fake_with_rng::<f64, _>(rng),
+                    "status": status,
+                    "items": (0..(1..=8).fake_with_rng::<i32, _>(rng)).map(|_| {
+                        json!({
+                            "product": company::en::Buzzword().fake_with_rng::<String, _>(rng),
+                            "quantity": (1..=10).fake_with_rng::<i32, _>(rng),
+                            "price": (5.0..=500.0).fake_with_rng::<f64, _>(rng)
+                        })
+                    }).collect::<Vec<_>>()
+                })
+            }).collect::<Vec<_>>()
+        });
+
+        WrappedJson(value)
+    }
+}
@@ -0,0 +1,80 @@
+//! Time to the first decrypted row of streamed queries
+//!
+//! The query bench times each streamed query once, with criterion recording
+//! the total time to read and decrypt every row. The time until the first
+//! decrypted row was available is taken from the same runs and recorded here,
+//! so it's written alongside the criterion output instead of running every
+//! query a second time.
+
+use crate::{Latencies, LatencySummary};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// Time to the first row of one scenario's streamed queries.
+#[derive(Debug, Serialize)]
+pub struct FirstRowStats {
+    pub scenario: String,
+    pub first_row: LatencySummary,
+}
+
+impl FirstRowStats {
+    pub fn new(scenario: impl Into<String>, first_row: &Latencies) -> Self {
+        Self {
+            scenario: scenario.into(),
+            first_row: first_row.summary(),
+        }
+    }
+}
+
+/// Time to the first row of every streamed scenario in a query bench.
+#[derive(Debug, Serialize)]
+pub struct FirstRowReport {
+    pub bench: String,
+    pub target_rows: String,
+    pub scenarios: Vec<FirstRowStats>,
+}
+
+impl FirstRowReport {
+    pub fn new(bench: impl Into<String>, target_rows: impl Into<String>) -> Self {
+        Self {
+            bench: bench.into(),
+            target_rows: target_rows.into(),
+            scenarios: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, stats: FirstRowStats) {
+        self.scenarios.push(stats);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenarios.is_empty()
+    }
+
+    /// Print a summary and write the report to `results/query/first_row_{bench}_rows_{n}.json`.
+    pub fn write(&self) -> Result<PathBuf> {
+        for s in &self.scenarios {
+            println!(
+                "{}/{}: first row mean {:.3}ms, p50 {:.3}ms, p99 {:.3}ms over {} queries",
+                self.bench,
+                s.scenario,
+                s.first_row.mean_ms,
+                s.first_row.p50_ms,
+                s.first_row.p99_ms,
+                s.first_row.count,
+            );
+        }
+
+        let path = PathBuf::from(format!(
+            "results/query/first_row_{}_rows_{}.json",
+            self.bench, self.target_rows
+        ));
+        fs::create_dir_all("results/query")?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+}
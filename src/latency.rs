@@ -0,0 +1,81 @@
+//! Latency histograms shared by the load and modification benchmarks
+
+use hdrhistogram::Histogram;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Longest latency a histogram records (an hour), in microseconds; longer ones are clamped.
+const MAX_LATENCY_MICROS: u64 = 3_600_000_000;
+
+/// Latencies recorded in an HDR histogram with microsecond resolution.
+#[derive(Debug, Clone)]
+pub struct Latencies {
+    histogram: Histogram<u64>,
+}
+
+impl Latencies {
+    pub fn new() -> Self {
+        Self {
+            histogram: Histogram::new_with_bounds(1, MAX_LATENCY_MICROS, 3)
+                .expect("latency histogram bounds are valid"),
+        }
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        self.histogram
+            .saturating_record(latency.as_micros().clamp(1, MAX_LATENCY_MICROS as u128) as u64);
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        self.histogram
+            .add(&other.histogram)
+            .expect("latency histograms have the same bounds");
+    }
+
+    pub fn len(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histogram.is_empty()
+    }
+
+    /// The underlying histogram, with values in microseconds.
+    pub fn histogram(&self) -> &Histogram<u64> {
+        &self.histogram
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        let ms = |micros: u64| micros as f64 / 1000.0;
+        let quantile = |q: f64| ms(self.histogram.value_at_quantile(q));
+        LatencySummary {
+            count: self.histogram.len(),
+            mean_ms: self.histogram.mean() / 1000.0,
+            min_ms: ms(self.histogram.min()),
+            p50_ms: quantile(0.5),
+            p95_ms: quantile(0.95),
+            p99_ms: quantile(0.99),
+            p999_ms: quantile(0.999),
+            max_ms: ms(self.histogram.max()),
+        }
+    }
+}
+
+impl Default for Latencies {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Latency percentiles of one scenario or operation, in milliseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub mean_ms: f64,
+    pub min_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub p999_ms: f64,
+    pub max_ms: f64,
+}
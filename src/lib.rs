@@ -1,29 +1,124 @@
 use anyhow::{Context, Result};
 use cipherstash_client::{
-    config::EnvSource,
-    credentials::ServiceCredentials,
-    encryption::{Plaintext, QueryOp, ScopedCipher},
+    encryption::Plaintext,
     eql::{decrypt_eql, encrypt_eql, EqlCiphertext, EqlOperation, Identifier, PreparedPlaintext},
     schema::{column::IndexType, ColumnConfig},
-    ZeroKMSConfig,
 };
-use fake::{Dummy, Fake};
+use fake::{
+    rand::{rngs::StdRng, SeedableRng},
+    Dummy, Fake,
+};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
 use serde_json::json;
-use sqlx::{postgres::PgPoolOptions, types::Json, QueryBuilder};
+use sqlx::{
+    postgres::{PgArguments, PgPoolOptions},
+    types::Json,
+};
 use std::borrow::Cow;
 use std::env;
 use std::fmt::Debug;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinSet;
+
+mod baseline;
+mod checkpoint;
+mod cipher;
+mod env_vars;
+mod fake_json;
+mod first_row;
+mod latency;
+mod load;
+mod local_kms;
+mod mixed;
+mod modify;
+mod param;
+mod param_pool;
+mod plan;
+mod scenario;
+mod server_stats;
+mod timing;
+mod verify;
+mod workload;
+
+pub use baseline::PlaintextQuery;
+pub use cipher::{init_scoped_cipher, Cipher, CipherBackend, CipherCredentials};
+pub use env_vars::{env_list_or, env_opt, env_or};
+pub use fake_json::{FakeJsonLarge, FakeJsonSmall};
+pub use first_row::{FirstRowReport, FirstRowStats};
+pub use latency::{Latencies, LatencySummary};
+pub use load::LoadMethod;
+pub use mixed::{
+    run_mixed_workload, MixedOptions, MixedReport, MixedTable, MixedWorkload, Operation,
+    OperationLoad, OperationMix, DEFAULT_OPERATION_RATIOS,
+};
+pub use modify::{
+    run_modification, IndexSet, Modification, ModificationResult, ModifyOptions, ModifyReport,
+    TableIndex,
+};
+pub use param::{BoundParam, Param, ParamOp, QueryParam};
+pub use param_pool::{
+    Distribution, ParamTiming, PoolOptions, PoolProfile, PoolReport, QueryPool, Spread,
+    DEFAULT_POOL_SIZE,
+};
+pub use plan::{IndexCheck, PlanReport, PlanScan, QueryPlan};
+pub use scenario::{
+    ColumnSpec, DecryptType, IndexKind, Scenario, ScenarioFile, ScenarioParam, DEFAULT_SCENARIO_DIR,
+};
+pub use server_stats::{
+    init_stat_statements, reset_stat_statements, stat_statements_enabled, ServerStats,
+    ServerStatsReport,
+};
+pub use verify::{verify_enabled, verify_query, Verification, VerificationReport};
+pub use workload::{
+    run_load_test, run_open_loop, Arrivals, LoadTestOptions, LoadTestReport, MixEntry,
+    OpenLoopOptions, OpenLoopReport, OpenLoopRun, OpenLoopScenario, QueryMix, ScenarioLoad,
+    DEFAULT_WORKLOAD_SCENARIOS,
+};
 
-pub async fn init_scoped_cipher() -> Result<Arc<ScopedCipher<ServiceCredentials>>> {
-    let client = ZeroKMSConfig::builder()
-        .add_source(EnvSource::new())
-        .build_with_client_key()
-        .context("failed to build config")?
-        .create_client();
+use checkpoint::{Batch, Checkpoint};
+use load::BatchWriter;
+use param::QueryParamKind;
+use serde_json::value::RawValue;
+use timing::{BatchTimings, PhaseTotals};
 
-    let scoped_cipher = ScopedCipher::init_default(Arc::new(client)).await?;
-    Ok(Arc::new(scoped_cipher))
+/// How the generate, encrypt and insert stages of an ingest run are scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IngestMode {
+    /// Each batch is generated, encrypted and inserted before the next one starts.
+    #[default]
+    Sequential,
+    /// Stages run concurrently, connected by bounded channels, with
+    /// `encrypt_concurrency` encryption workers and `insert_concurrency` insert workers.
+    Pipelined,
+}
+
+impl IngestMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sequential => "sequential",
+            Self::Pipelined => "pipelined",
+        }
+    }
+}
+
+impl FromStr for IngestMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "sequential" => Ok(Self::Sequential),
+            "pipelined" => Ok(Self::Pipelined),
+            other => {
+                anyhow::bail!("ingest mode must be one of: sequential, pipelined (got {other:?})")
+            }
+        }
+    }
 }
 
 pub struct IngestOptions {
@@ -32,6 +127,17 @@ pub struct IngestOptions {
     pub batch_size: usize,
     pub identifier: Identifier,
     pub column_config: ColumnConfig,
+    pub mode: IngestMode,
+    pub encrypt_concurrency: usize,
+    pub insert_concurrency: usize,
+    pub load_method: LoadMethod,
+    /// Resume from, and record progress to, the checkpoint with this ID.
+    pub checkpoint_id: Option<String>,
+    /// Seed for the generated plaintext stream.
+    pub seed: u64,
+    /// Unencrypted table that mirrors `identifier`, written when `write_plaintext` is set.
+    pub plaintext_identifier: Option<Identifier>,
+    pub write_plaintext: bool,
 }
 
 pub struct IngestOptionsBuilder {
@@ -40,11 +146,20 @@ pub struct IngestOptionsBuilder {
     batch_size: Option<usize>,
     identifier: Option<Identifier>,
     column_config: Option<ColumnConfig>,
+    mode: Option<IngestMode>,
+    encrypt_concurrency: Option<usize>,
+    insert_concurrency: Option<usize>,
+    load_method: Option<LoadMethod>,
+    checkpoint_id: Option<String>,
+    seed: Option<u64>,
+    plaintext_identifier: Option<Identifier>,
+    write_plaintext: Option<bool>,
 }
 
 impl IngestOptionsBuilder {
     const DEFAULT_BATCH_SIZE: usize = 1000;
     const DEFAULT_NUM_RECORDS: i32 = 100_000;
+    const DEFAULT_CONCURRENCY: usize = 4;
 
     pub fn new(bench_name: impl Into<String>) -> Self {
         Self {
@@ -53,9 +168,60 @@ impl IngestOptionsBuilder {
             batch_size: None,
             identifier: None,
             column_config: None,
+            mode: None,
+            encrypt_concurrency: None,
+            insert_concurrency: None,
+            load_method: None,
+            checkpoint_id: None,
+            seed: None,
+            plaintext_identifier: None,
+            write_plaintext: None,
         }
     }
 
+    /// Apply overrides from the environment:
+    /// - INGEST_MODE: `sequential` (default) or `pipelined`
+    /// - ENCRYPT_CONCURRENCY: encryption workers in pipelined mode (default: 4)
+    /// - INSERT_CONCURRENCY: insert workers in pipelined mode (default: 4)
+    /// - LOAD_METHOD: `insert` (default), `copy_text` or `copy_binary`
+    /// - CHECKPOINT_ID: make the run resumable under this ID
+    /// - SEED: seed for the generated data (default: random)
+    /// - WRITE_PLAINTEXT: `true` to also write each value to the plaintext table (default: false)
+    pub fn env_overrides(mut self) -> Result<Self> {
+        if let Ok(mode) = env::var("INGEST_MODE") {
+            self.mode = Some(mode.parse()?);
+        }
+        if let Ok(n) = env::var("ENCRYPT_CONCURRENCY") {
+            self.encrypt_concurrency = Some(
+                n.parse()
+                    .context("ENCRYPT_CONCURRENCY must be a valid integer")?,
+            );
+        }
+        if let Ok(n) = env::var("INSERT_CONCURRENCY") {
+            self.insert_concurrency = Some(
+                n.parse()
+                    .context("INSERT_CONCURRENCY must be a valid integer")?,
+            );
+        }
+        if let Ok(method) = env::var("LOAD_METHOD") {
+            self.load_method = Some(method.parse()?);
+        }
+        if let Ok(id) = env::var("CHECKPOINT_ID") {
+            self.checkpoint_id = Some(id);
+        }
+        if let Ok(seed) = env::var("SEED") {
+            self.seed = Some(seed.parse().context("SEED must be a valid u64")?);
+        }
+        if let Ok(write) = env::var("WRITE_PLAINTEXT") {
+            self.write_plaintext = Some(
+                write
+                    .parse()
+                    .context("WRITE_PLAINTEXT must be `true` or `false`")?,
+            );
+        }
+        Ok(self)
+    }
+
     pub fn num_records(mut self, num_records: i32) -> Self {
         self.num_records = Some(num_records);
         self
@@ -76,92 +242,513 @@ impl IngestOptionsBuilder {
         self
     }
 
+    pub fn mode(mut self, mode: IngestMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    pub fn encrypt_concurrency(mut self, encrypt_concurrency: usize) -> Self {
+        self.encrypt_concurrency = Some(encrypt_concurrency);
+        self
+    }
+
+    pub fn insert_concurrency(mut self, insert_concurrency: usize) -> Self {
+        self.insert_concurrency = Some(insert_concurrency);
+        self
+    }
+
+    pub fn load_method(mut self, load_method: LoadMethod) -> Self {
+        self.load_method = Some(load_method);
+        self
+    }
+
+    /// Write each batch in a transaction that also records a checkpoint.
+    ///
+    /// If an earlier run with the same ID didn't complete, this run resumes it
+    /// with the original `num_records` and `batch_size`, skipping the batches
+    /// that were already committed.
+    pub fn checkpoint_id(mut self, checkpoint_id: impl Into<String>) -> Self {
+        self.checkpoint_id = Some(checkpoint_id.into());
+        self
+    }
+
+    /// Generate the plaintext stream from this seed.
+    ///
+    /// Each record is generated from the seed and its position in the stream,
+    /// so the same seed produces the same data regardless of ingest mode,
+    /// batch size or resumption. Without a seed a random one is chosen and
+    /// recorded in the results.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// The unencrypted table that mirrors the encrypted one, such as `integer_plaintext`.
+    pub fn plaintext_identifier(mut self, plaintext_identifier: Identifier) -> Self {
+        self.plaintext_identifier = Some(plaintext_identifier);
+        self
+    }
+
+    /// Also write each generated value to the plaintext table, under the same
+    /// `id` as its encrypted row.
+    pub fn write_plaintext(mut self, write_plaintext: bool) -> Self {
+        self.write_plaintext = Some(write_plaintext);
+        self
+    }
+
     pub fn build(self) -> Result<IngestOptions> {
+        let encrypt_concurrency = self
+            .encrypt_concurrency
+            .unwrap_or(Self::DEFAULT_CONCURRENCY);
+        let insert_concurrency = self.insert_concurrency.unwrap_or(Self::DEFAULT_CONCURRENCY);
+
+        if encrypt_concurrency == 0 || insert_concurrency == 0 {
+            anyhow::bail!("encrypt_concurrency and insert_concurrency must be at least 1");
+        }
+
+        let write_plaintext = self.write_plaintext.unwrap_or_default();
+        if write_plaintext && self.plaintext_identifier.is_none() {
+            anyhow::bail!("plaintext_identifier is required to write plaintext");
+        }
+
         Ok(IngestOptions {
             bench_name: self.bench_name,
             num_records: self.num_records.unwrap_or(Self::DEFAULT_NUM_RECORDS),
             batch_size: self.batch_size.unwrap_or(Self::DEFAULT_BATCH_SIZE),
             identifier: self.identifier.context("identifier is required")?,
             column_config: self.column_config.context("column_config is required")?,
+            mode: self.mode.unwrap_or_default(),
+            encrypt_concurrency,
+            insert_concurrency,
+            load_method: self.load_method.unwrap_or_default(),
+            checkpoint_id: self.checkpoint_id,
+            seed: self.seed.unwrap_or_else(rand::random),
+            plaintext_identifier: self.plaintext_identifier,
+            write_plaintext,
         })
     }
 }
 
 impl IngestOptions {
-    pub async fn ingest<T, F>(self, f: F) -> Result<()>
+    pub async fn ingest<T, F>(mut self, f: F) -> Result<()>
     where
         T: Into<Plaintext> + Dummy<F> + Send + Debug,
     {
         let database_url =
             env::var("DATABASE_URL").context("DATABASE_URL environment variable must be set")?;
 
-        let num_records: i32 = env::var("NUM_RECORDS")
-            .unwrap_or_else(|_| "10000".to_string())
-            .parse()
-            .expect("NUM_RECORDS must be a valid integer");
-
         let hf_iteration: i32 = env::var("HYPERFINE_ITERATION")
             .unwrap_or_else(|_| "0".to_string())
             .parse()
             .expect("HYPERFINE_ITERATION must be a valid integer");
 
+        let max_connections = match self.mode {
+            IngestMode::Sequential => 5,
+            IngestMode::Pipelined => self.insert_concurrency.max(5) as u32,
+        };
+
         let pool = PgPoolOptions::new()
-            .max_connections(5)
+            .max_connections(max_connections)
             .connect(&database_url)
             .await?;
 
-        let client = ZeroKMSConfig::builder()
-            .add_source(EnvSource::new())
-            .build_with_client_key()
-            .expect("failed to build config")
-            .create_client();
-
-        let scoped_cipher = ScopedCipher::init_default(Arc::new(client)).await?;
-        let scoped_cipher = Arc::new(scoped_cipher);
-
-        let column_config = Cow::Borrowed(&self.column_config);
-
-        for batch_start in (0..self.num_records).step_by(self.batch_size) {
-            let batch_end = (batch_start + self.batch_size as i32).min(self.num_records);
-            let batch_count = batch_end - batch_start;
-
-            let prepared = (0..batch_count)
-                .map(|_| {
-                    let x: T = f.fake();
+        let checkpoint = match &self.checkpoint_id {
+            Some(id) => {
+                let checkpoint = Checkpoint::load_or_start(
+                    &pool,
+                    id,
+                    self.num_records,
+                    self.batch_size,
+                    self.seed,
+                )
+                .await?;
+                if checkpoint.is_resumed() {
+                    println!(
+                        "Resuming {} with {} of {} rows already committed",
+                        checkpoint.id(),
+                        checkpoint.rows_committed(),
+                        checkpoint.num_records
+                    );
+                }
+                self.num_records = checkpoint.num_records;
+                self.batch_size = checkpoint.batch_size;
+                self.seed = checkpoint.seed;
+                Some(Arc::new(checkpoint))
+            }
+            None => None,
+        };
 
-                    PreparedPlaintext::new(
-                        // FIXME: take a reference instead of using Cow?
-                        column_config.clone(),
-                        // FIXME: take a reference instead of owning the identifier
-                        self.identifier.clone(),
-                        Plaintext::new(x),
-                        EqlOperation::Store,
-                    )
-                })
-                .collect::<Vec<_>>();
+        let scoped_cipher = init_scoped_cipher().await?;
+        let plaintext_identifier = match self.write_plaintext {
+            true => self.plaintext_identifier.clone(),
+            false => None,
+        };
+        let writer = BatchWriter::new(
+            &pool,
+            self.identifier.clone(),
+            self.load_method,
+            plaintext_identifier,
+        )
+        .await?;
 
-            let out = encrypt_eql(scoped_cipher.clone(), prepared, &Default::default()).await?;
+        let timings = match self.mode {
+            IngestMode::Sequential => {
+                self.ingest_sequential::<T, F>(
+                    &f,
+                    scoped_cipher,
+                    &writer,
+                    &pool,
+                    checkpoint.as_deref(),
+                )
+                .await?
+            }
+            IngestMode::Pipelined => {
+                self.ingest_pipelined::<T, F>(&f, scoped_cipher, &writer, &pool, checkpoint.clone())
+                    .await?
+            }
+        };
 
-            QueryBuilder::new(format!("INSERT INTO {} (value) ", self.identifier.table()))
-                .push_values(out.into_iter(), |mut b, v| {
-                    b.push_bind(Json(v));
-                })
-                .build()
-                .execute(&pool)
-                .await?;
+        if let Some(checkpoint) = &checkpoint {
+            checkpoint.complete(&pool).await?;
         }
 
+        // Sequential runs only ever have one batch in flight
+        let (encrypt_concurrency, insert_concurrency) = match self.mode {
+            IngestMode::Sequential => (1, 1),
+            IngestMode::Pipelined => (self.encrypt_concurrency, self.insert_concurrency),
+        };
+
+        // A resumed run skips the batches an earlier run committed, so only
+        // count the rows written by this one
+        let inserted: usize = timings.iter().map(|t| t.rows).sum();
         let result = json!({
-            "inserted": num_records
+            "inserted": inserted,
+            "num_records": self.num_records,
+            "ingest_mode": self.mode.as_str(),
+            "encrypt_concurrency": encrypt_concurrency,
+            "insert_concurrency": insert_concurrency,
+            "load_method": self.load_method.as_str(),
+            "seed": self.seed,
+            "write_plaintext": self.write_plaintext,
+            "phases": PhaseTotals::sum(&timings),
+            "batches": timings,
         });
         let filename = format!(
-            "target/{}-{num_records}_{hf_iteration}.json",
-            self.bench_name
+            "target/{}-{}_{hf_iteration}.json",
+            self.result_name(),
+            self.num_records
         );
         std::fs::write(&filename, serde_json::to_string(&result)?)?;
 
         Ok(())
     }
+
+    /// Name used for result files, COPY runs are kept apart from INSERT runs so they can be compared.
+    pub fn result_name(&self) -> String {
+        match self.load_method {
+            LoadMethod::Insert => self.bench_name.clone(),
+            method => format!("{}_{}", self.bench_name, method.as_str()),
+        }
+    }
+
+    /// Batches for the whole run, the last batch may be short.
+    fn batches(&self) -> impl Iterator<Item = Batch> + '_ {
+        (0..self.num_records)
+            .step_by(self.batch_size)
+            .enumerate()
+            .map(|(index, batch_start)| {
+                let batch_end = (batch_start + self.batch_size as i32).min(self.num_records);
+                Batch {
+                    index,
+                    seed_position: batch_start as usize,
+                    len: (batch_end - batch_start) as usize,
+                }
+            })
+    }
+
+    /// Batches that still need to be written, skipping any committed by an earlier attempt.
+    fn pending_batches<'a>(
+        &'a self,
+        checkpoint: Option<&'a Checkpoint>,
+    ) -> impl Iterator<Item = Batch> + 'a {
+        self.batches()
+            .filter(move |batch| !checkpoint.is_some_and(|c| c.is_committed(batch)))
+    }
+
+    async fn ingest_sequential<T, F>(
+        &self,
+        f: &F,
+        scoped_cipher: Arc<Cipher>,
+        writer: &BatchWriter,
+        pool: &sqlx::PgPool,
+        checkpoint: Option<&Checkpoint>,
+    ) -> Result<Vec<BatchTimings>>
+    where
+        T: Into<Plaintext> + Dummy<F> + Send + Debug,
+    {
+        let mut timings = Vec::new();
+
+        for batch in self.pending_batches(checkpoint) {
+            let mut batch_timings = BatchTimings::new(&batch);
+
+            let started = Instant::now();
+            let plaintexts = generate_batch::<T, F>(f, self.seed, &batch);
+            batch_timings.generate_seconds = started.elapsed().as_secs_f64();
+
+            let shadow = writer.writes_plaintext().then(|| plaintexts.clone());
+
+            let started = Instant::now();
+            let out = encrypt_batch(
+                scoped_cipher.clone(),
+                &self.column_config,
+                &self.identifier,
+                plaintexts,
+            )
+            .await?;
+            batch_timings.encrypt_seconds = started.elapsed().as_secs_f64();
+
+            let started = Instant::now();
+            let serialized = load::serialize(&out)?;
+            batch_timings.serialize_seconds = started.elapsed().as_secs_f64();
+
+            let started = Instant::now();
+            commit_batch(pool, writer, checkpoint, &batch, serialized, shadow).await?;
+            batch_timings.insert_seconds = started.elapsed().as_secs_f64();
+
+            timings.push(batch_timings);
+        }
+
+        Ok(timings)
+    }
+
+    /// Run the generate, encrypt and insert stages concurrently.
+    ///
+    /// Plaintext batches are generated on the calling task and handed to a pool
+    /// of encryption workers, which encrypt and serialize them and hand them on
+    /// to a pool of insert workers. Both channels are bounded so that generation
+    /// can't run arbitrarily far ahead of the database.
+    async fn ingest_pipelined<T, F>(
+        &self,
+        f: &F,
+        scoped_cipher: Arc<Cipher>,
+        writer: &BatchWriter,
+        pool: &sqlx::PgPool,
+        checkpoint: Option<Arc<Checkpoint>>,
+    ) -> Result<Vec<BatchTimings>>
+    where
+        T: Into<Plaintext> + Dummy<F> + Send + Debug,
+    {
+        let (plaintext_tx, plaintext_rx) =
+            mpsc::channel::<(Batch, Vec<Plaintext>, BatchTimings)>(self.encrypt_concurrency);
+        let (ciphertext_tx, ciphertext_rx) =
+            mpsc::channel::<SerializedBatch>(self.insert_concurrency);
+
+        let plaintext_rx = Arc::new(Mutex::new(plaintext_rx));
+        let ciphertext_rx = Arc::new(Mutex::new(ciphertext_rx));
+        let column_config = Arc::new(self.column_config.clone());
+
+        let mut encrypt_workers = JoinSet::new();
+        for _ in 0..self.encrypt_concurrency {
+            let rx = plaintext_rx.clone();
+            let tx = ciphertext_tx.clone();
+            let scoped_cipher = scoped_cipher.clone();
+            let column_config = column_config.clone();
+            let identifier = self.identifier.clone();
+            let writes_plaintext = writer.writes_plaintext();
+
+            encrypt_workers.spawn(async move {
+                loop {
+                    let Some((batch, plaintexts, mut timings)) = rx.lock().await.recv().await
+                    else {
+                        break;
+                    };
+                    let shadow = writes_plaintext.then(|| plaintexts.clone());
+
+                    let started = Instant::now();
+                    let out = encrypt_batch(
+                        scoped_cipher.clone(),
+                        &column_config,
+                        &identifier,
+                        plaintexts,
+                    )
+                    .await?;
+                    timings.encrypt_seconds = started.elapsed().as_secs_f64();
+
+                    let started = Instant::now();
+                    let serialized = load::serialize(&out)?;
+                    timings.serialize_seconds = started.elapsed().as_secs_f64();
+
+                    tx.send((batch, serialized, shadow, timings))
+                        .await
+                        .context("insert workers stopped before ingest finished")?;
+                }
+                Ok::<_, anyhow::Error>(())
+            });
+        }
+        // Only the workers hold senders now, so inserts finish once encryption does
+        drop(ciphertext_tx);
+
+        let mut insert_workers = JoinSet::new();
+        for _ in 0..self.insert_concurrency {
+            let rx = ciphertext_rx.clone();
+            let pool = pool.clone();
+            let writer = writer.clone();
+            let checkpoint = checkpoint.clone();
+
+            insert_workers.spawn(async move {
+                let mut completed = Vec::new();
+                loop {
+                    let Some((batch, ciphertexts, plaintexts, mut timings)) =
+                        rx.lock().await.recv().await
+                    else {
+                        break;
+                    };
+
+                    let started = Instant::now();
+                    commit_batch(
+                        &pool,
+                        &writer,
+                        checkpoint.as_deref(),
+                        &batch,
+                        ciphertexts,
+                        plaintexts,
+                    )
+                    .await?;
+                    timings.insert_seconds = started.elapsed().as_secs_f64();
+
+                    completed.push(timings);
+                }
+                Ok::<_, anyhow::Error>(completed)
+            });
+        }
+        drop(ciphertext_rx);
+        drop(plaintext_rx);
+
+        let mut generated = Ok(());
+        for batch in self.pending_batches(checkpoint.as_deref()) {
+            let mut timings = BatchTimings::new(&batch);
+            let started = Instant::now();
+            let plaintexts = generate_batch::<T, F>(f, self.seed, &batch);
+            timings.generate_seconds = started.elapsed().as_secs_f64();
+
+            if plaintext_tx
+                .send((batch, plaintexts, timings))
+                .await
+                .is_err()
+            {
+                generated = Err(anyhow::anyhow!(
+                    "encryption workers stopped before ingest finished"
+                ));
+                break;
+            }
+        }
+        drop(plaintext_tx);
+
+        // Report worker failures ahead of the closed-channel errors they cause upstream
+        let completed = join_workers(insert_workers).await?;
+        join_workers(encrypt_workers).await?;
+        generated?;
+
+        let mut timings: Vec<_> = completed.into_iter().flatten().collect();
+        timings.sort_by_key(|t| t.batch_index);
+        Ok(timings)
+    }
+}
+
+/// A batch ready to be written: serialized ciphertexts, the plaintexts for the
+/// plaintext table if there is one, and the timings so far.
+type SerializedBatch = (
+    Batch,
+    Vec<Box<RawValue>>,
+    Option<Vec<Plaintext>>,
+    BatchTimings,
+);
+
+fn generate_batch<T, F>(f: &F, seed: u64, batch: &Batch) -> Vec<Plaintext>
+where
+    T: Into<Plaintext> + Dummy<F>,
+{
+    (batch.seed_position..batch.seed_position + batch.len)
+        .map(|position| {
+            let x: T = f.fake_with_rng(&mut record_rng(seed, position));
+            Plaintext::new(x)
+        })
+        .collect()
+}
+
+/// An RNG for the record at `position` in the stream generated from `seed`.
+///
+/// Seeding per record, rather than per run, lets batches be generated
+/// independently and in any order.
+fn record_rng(seed: u64, position: usize) -> StdRng {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&seed.to_le_bytes());
+    hasher.update(&(position as u64).to_le_bytes());
+    StdRng::from_seed(*hasher.finalize().as_bytes())
+}
+
+async fn encrypt_batch(
+    scoped_cipher: Arc<Cipher>,
+    column_config: &ColumnConfig,
+    identifier: &Identifier,
+    plaintexts: Vec<Plaintext>,
+) -> Result<Vec<EqlCiphertext>> {
+    let prepared = plaintexts
+        .into_iter()
+        .map(|plaintext| {
+            PreparedPlaintext::new(
+                Cow::Borrowed(column_config),
+                // FIXME: take a reference instead of owning the identifier
+                identifier.clone(),
+                plaintext,
+                EqlOperation::Store,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    Ok(encrypt_eql(scoped_cipher, prepared, &Default::default()).await?)
+}
+
+/// Write a batch.
+///
+/// When the run is resumable or has a plaintext table, the batch is written in
+/// a transaction so its checkpoint and plaintext rows commit with it.
+async fn commit_batch(
+    pool: &sqlx::PgPool,
+    writer: &BatchWriter,
+    checkpoint: Option<&Checkpoint>,
+    batch: &Batch,
+    ciphertexts: Vec<Box<RawValue>>,
+    plaintexts: Option<Vec<Plaintext>>,
+) -> Result<()> {
+    if checkpoint.is_none() && plaintexts.is_none() {
+        let mut conn = pool.acquire().await?;
+        return writer.write(&mut conn, ciphertexts, None).await;
+    }
+
+    let mut tx = pool.begin().await?;
+    writer.write(&mut tx, ciphertexts, plaintexts).await?;
+    if let Some(checkpoint) = checkpoint {
+        checkpoint.record(&mut tx, batch).await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Wait for every worker, returning their outputs or the first error.
+async fn join_workers<T: 'static>(mut workers: JoinSet<Result<T>>) -> Result<Vec<T>> {
+    let mut outputs = Vec::new();
+    let mut first_error = None;
+    while let Some(joined) = workers.join_next().await {
+        match joined.context("ingest worker panicked")? {
+            Ok(output) => outputs.push(output),
+            Err(err) => {
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    first_error.map_or(Ok(outputs), Err)
 }
 
 #[derive(Debug)]
@@ -178,6 +765,7 @@ pub struct EncryptedQueryBuilder {
     pub identifier: Identifier,
     pub index_type: Option<IndexType>,
     pub statement: Option<String>,
+    pub params: Vec<QueryParam>,
 }
 
 impl EncryptedQueryBuilder {
@@ -187,9 +775,11 @@ impl EncryptedQueryBuilder {
             identifier,
             index_type: None,
             statement: None,
+            params: Vec::new(),
         }
     }
 
+    /// The index type encrypted parameters are encrypted for, unless they set their own.
     pub fn index_type(mut self, index_type: IndexType) -> Self {
         self.index_type = Some(index_type);
         self
@@ -200,68 +790,291 @@ impl EncryptedQueryBuilder {
         self
     }
 
-    pub async fn build_query<T>(
-        self,
-        plaintext: T,
-        cipher: Arc<ScopedCipher<ServiceCredentials>>,
-    ) -> Result<EncryptedQuery>
+    /// Add the next parameter (`$1`, `$2`, ...), see [`QueryParam`].
+    pub fn param(mut self, param: QueryParam) -> Self {
+        self.params.push(param);
+        self
+    }
+
+    /// Build a query with a single encrypted parameter, `$1`.
+    pub async fn build_query<T>(self, plaintext: T, cipher: Arc<Cipher>) -> Result<EncryptedQuery>
     where
         T: Into<Plaintext> + Send + Debug,
     {
-        let index_type = self
-            .index_type
-            .context("index_type must be set to build query")?;
+        self.param(QueryParam::encrypted(plaintext))
+            .build(cipher)
+            .await
+    }
 
-        let prepared = PreparedPlaintext::new(
-            Cow::Owned(self.column_config),
-            self.identifier.clone(),
-            plaintext.into(),
-            EqlOperation::Query(&index_type, QueryOp::Default),
-        );
+    /// Encrypt every encrypted parameter and build the query.
+    pub async fn build(self, cipher: Arc<Cipher>) -> Result<EncryptedQuery> {
+        let statement = self.statement.context("statement must be set")?;
+        let names: Vec<Option<&str>> = self.params.iter().map(|p| p.name.as_deref()).collect();
+        let statement = param::positional_statement(&statement, &names)?;
+
+        let mut index_types = Vec::new();
+        for param in &self.params {
+            if let QueryParamKind::Encrypted { index_type, .. } = &param.kind {
+                let index_type = index_type
+                    .clone()
+                    .or_else(|| self.index_type.clone())
+                    .context("index_type must be set to build query")?;
+                index_types.push(index_type);
+            }
+        }
+
+        let mut bound = Vec::with_capacity(self.params.len());
+        let mut prepared = Vec::with_capacity(index_types.len());
+        let mut index_types = index_types.iter();
+        for param in self.params {
+            match param.kind {
+                QueryParamKind::Encrypted { plaintext, op, .. } => {
+                    let index_type = index_types.next().expect("one index type per parameter");
+                    prepared.push(PreparedPlaintext::new(
+                        Cow::Borrowed(&self.column_config),
+                        self.identifier.clone(),
+                        plaintext,
+                        op.eql_operation(index_type),
+                    ));
+                    bound.push(None);
+                }
+                QueryParamKind::Plain(value) => bound.push(Some(BoundParam::Plain(value))),
+            }
+        }
 
-        let mut out = encrypt_eql(Arc::clone(&cipher), vec![prepared], &Default::default()).await?;
+        let mut encrypted = encrypt_eql(Arc::clone(&cipher), prepared, &Default::default())
+            .await?
+            .into_iter();
+
+        let params = bound
+            .into_iter()
+            .map(|param| {
+                param.unwrap_or_else(|| {
+                    BoundParam::Encrypted(Box::new(
+                        encrypted.next().expect("one ciphertext per parameter"),
+                    ))
+                })
+            })
+            .collect();
 
         Ok(EncryptedQuery {
-            eql: out.remove(0),
-            statement: self.statement.context("statement must be set")?,
+            statement,
+            params,
             scoped_cipher: cipher,
         })
     }
 }
 
 pub struct EncryptedQuery {
-    pub eql: EqlCiphertext,
     pub statement: String,
-    scoped_cipher: Arc<ScopedCipher<ServiceCredentials>>,
+    pub params: Vec<BoundParam>,
+    scoped_cipher: Arc<Cipher>,
 }
 
 impl EncryptedQuery {
     pub async fn execute(&self, pool: &sqlx::PgPool) -> Result<Vec<(i32, Json<EqlCiphertext>)>> {
-        let results: Vec<(i32, Json<EqlCiphertext>)> = sqlx::query_as(&self.statement)
-            .bind(Json(&self.eql))
-            .fetch_all(pool)
-            .await?;
+        let results: Vec<(i32, Json<EqlCiphertext>)> =
+            sqlx::query_as_with(&self.statement, self.arguments()?)
+                .fetch_all(pool)
+                .await?;
 
         Ok(results)
     }
 
+    /// The query's parameters, ready to bind.
+    pub fn arguments(&self) -> Result<PgArguments> {
+        let mut args = PgArguments::default();
+        for param in &self.params {
+            param.add_to(&mut args)?;
+        }
+        Ok(args)
+    }
+
     pub async fn execute_and_decrypt<T>(&self, pool: &sqlx::PgPool) -> Result<Vec<T>>
+    where
+        T: TryFrom<Plaintext>,
+        <T as TryFrom<Plaintext>>::Error: Debug,
+    {
+        let results = self.execute_and_decrypt_with_ids(pool).await?;
+
+        Ok(results.into_iter().map(|(_, value)| value).collect())
+    }
+
+    /// Like [`Self::execute_and_decrypt`], but keeps the `id` of each row.
+    pub async fn execute_and_decrypt_with_ids<T>(
+        &self,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<(i32, T)>>
     where
         T: TryFrom<Plaintext>,
         <T as TryFrom<Plaintext>>::Error: Debug,
     {
         let results: Vec<(i32, Json<EqlCiphertext>)> = self.execute(pool).await?;
+        self.decrypt_rows(results).await
+    }
 
-        let decrypted = decrypt_eql(
-            Arc::clone(&self.scoped_cipher),
-            results.into_iter().map(|(_, value)| value.0),
-            &Default::default(),
-        )
-        .await?
-        .into_iter()
-        .map(|pt| T::try_from(pt).expect("failed to convert plaintext"))
-        .collect();
+    /// Stream rows as Postgres returns them, rather than buffering the whole result.
+    pub fn fetch<'a>(
+        &'a self,
+        pool: &'a sqlx::PgPool,
+    ) -> BoxStream<'a, Result<(i32, Json<EqlCiphertext>)>> {
+        match self.arguments() {
+            Ok(args) => sqlx::query_as_with(&self.statement, args)
+                .fetch(pool)
+                .map_err(anyhow::Error::from)
+                .boxed(),
+            Err(err) => stream::once(async { Err(err) }).boxed(),
+        }
+    }
+
+    /// Stream decrypted rows, decrypting the rows that have arrived (up to
+    /// `chunk_size` at a time) while later rows are still being read.
+    pub fn fetch_and_decrypt<'a, T>(
+        &'a self,
+        pool: &'a sqlx::PgPool,
+        chunk_size: usize,
+    ) -> BoxStream<'a, Result<(i32, T)>>
+    where
+        T: TryFrom<Plaintext> + Send + 'a,
+        <T as TryFrom<Plaintext>>::Error: Debug,
+    {
+        self.fetch(pool)
+            .ready_chunks(chunk_size.max(1))
+            .then(move |chunk| async move {
+                let rows = chunk.into_iter().collect::<Result<Vec<_>>>()?;
+                self.decrypt_rows::<T>(rows).await
+            })
+            .map_ok(|rows| stream::iter(rows.into_iter().map(Ok)))
+            .try_flatten()
+            .boxed()
+    }
+
+    /// Drain [`Self::fetch_and_decrypt`], timing the first row and the whole result.
+    pub async fn time_fetch_and_decrypt<T>(
+        &self,
+        pool: &sqlx::PgPool,
+        chunk_size: usize,
+    ) -> Result<StreamTiming>
+    where
+        T: TryFrom<Plaintext> + Send,
+        <T as TryFrom<Plaintext>>::Error: Debug,
+    {
+        let started = Instant::now();
+        let mut rows = self.fetch_and_decrypt::<T>(pool, chunk_size);
+        let mut first_row = None;
+        let mut count = 0;
+
+        while let Some(row) = rows.next().await {
+            row?;
+            first_row.get_or_insert_with(|| started.elapsed());
+            count += 1;
+        }
+
+        let total = started.elapsed();
+        Ok(StreamTiming {
+            first_row: first_row.unwrap_or(total),
+            total,
+            rows: count,
+        })
+    }
+
+    async fn decrypt_rows<T>(&self, rows: Vec<(i32, Json<EqlCiphertext>)>) -> Result<Vec<(i32, T)>>
+    where
+        T: TryFrom<Plaintext>,
+        <T as TryFrom<Plaintext>>::Error: Debug,
+    {
+        let (ids, values): (Vec<_>, Vec<_>) =
+            rows.into_iter().map(|(id, value)| (id, value.0)).unzip();
+
+        let decrypted = decrypt_eql(Arc::clone(&self.scoped_cipher), values, &Default::default())
+            .await?
+            .into_iter()
+            .map(|pt| T::try_from(pt).expect("failed to convert plaintext"));
+
+        Ok(ids.into_iter().zip(decrypted).collect())
+    }
+}
+
+/// Timings of a streamed query.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamTiming {
+    /// Time until the first decrypted row, or the total time if there were no rows.
+    pub first_row: Duration,
+    pub total: Duration,
+    pub rows: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cipherstash_client::schema::ColumnType;
+    use fake::{faker::name::raw::Name, locales::EN, rand::RngCore};
+
+    fn options(mode: IngestMode, batch_size: usize, seed: u64) -> IngestOptions {
+        IngestOptionsBuilder::new("test")
+            .num_records(100)
+            .batch_size(batch_size)
+            .identifier(Identifier::new("string_encrypted", "value"))
+            .column_config(ColumnConfig::build("value").casts_as(ColumnType::Utf8Str))
+            .mode(mode)
+            .seed(seed)
+            .build()
+            .unwrap()
+    }
+
+    /// Every record of a run, generated batch by batch in `order` and put back in stream order.
+    fn records(
+        options: &IngestOptions,
+        order: impl Fn(Vec<Batch>) -> Vec<Batch>,
+    ) -> Vec<Plaintext> {
+        let mut generated: Vec<(usize, Vec<Plaintext>)> = order(options.batches().collect())
+            .into_iter()
+            .map(|batch| {
+                let plaintexts = generate_batch::<String, _>(&Name(EN), options.seed, &batch);
+                (batch.seed_position, plaintexts)
+            })
+            .collect();
+        generated.sort_by_key(|(position, _)| *position);
+        generated
+            .into_iter()
+            .flat_map(|(_, plaintexts)| plaintexts)
+            .collect()
+    }
+
+    #[test]
+    fn record_rng_depends_only_on_the_seed_and_position() {
+        assert_eq!(record_rng(7, 42).next_u64(), record_rng(7, 42).next_u64());
+        assert_ne!(record_rng(7, 42).next_u64(), record_rng(7, 43).next_u64());
+        assert_ne!(record_rng(7, 42).next_u64(), record_rng(8, 42).next_u64());
+    }
+
+    #[test]
+    fn record_rng_gives_the_same_record_for_the_same_seed_and_position() {
+        let a: i32 = fake::Faker.fake_with_rng(&mut record_rng(7, 42));
+        let b: i32 = fake::Faker.fake_with_rng(&mut record_rng(7, 42));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn pipelined_and_sequential_ingests_generate_the_same_records() {
+        let sequential = options(IngestMode::Sequential, 10, 7);
+        let pipelined = options(IngestMode::Pipelined, 3, 7);
+
+        let expected = records(&sequential, |batches| batches);
+        // Pipelined batches can be generated and committed in any order
+        let actual = records(&pipelined, |mut batches| {
+            batches.reverse();
+            batches
+        });
+
+        assert_eq!(expected.len(), 100);
+        assert_eq!(expected, actual);
+    }
 
-        Ok(decrypted)
+    #[test]
+    fn different_seeds_generate_different_records() {
+        let a = records(&options(IngestMode::Sequential, 10, 7), |batches| batches);
+        let b = records(&options(IngestMode::Sequential, 10, 8), |batches| batches);
+        assert_ne!(a, b);
     }
 }
@@ -0,0 +1,478 @@
+//! Writers for loading batches of encrypted values into Postgres
+//!
+//! Batches can be loaded with multi-row `INSERT` statements or with
+//! `COPY ... FROM STDIN` in either text or binary format. COPY bypasses the
+//! implicit `jsonb` to `eql_v2_encrypted` cast used by INSERT, so the column
+//! type is inspected once per run and each value is encoded to match it.
+//!
+//! When a plaintext table is configured, ids for each batch are reserved from
+//! the encrypted table's sequence and the plaintext values are written to the
+//! plaintext table under the same ids.
+
+use anyhow::{bail, Context, Result};
+use cipherstash_client::{
+    encryption::Plaintext,
+    eql::{EqlCiphertext, Identifier},
+};
+use serde_json::value::RawValue;
+use sqlx::{types::Json, PgConnection, PgPool, Postgres, QueryBuilder};
+use std::str::FromStr;
+
+/// How batches of encrypted values are written to the target table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoadMethod {
+    /// Multi-row `INSERT ... VALUES` statements.
+    #[default]
+    Insert,
+    /// `COPY ... FROM STDIN WITH (FORMAT text)`.
+    CopyText,
+    /// `COPY ... FROM STDIN WITH (FORMAT binary)`.
+    CopyBinary,
+}
+
+impl LoadMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Insert => "insert",
+            Self::CopyText => "copy_text",
+            Self::CopyBinary => "copy_binary",
+        }
+    }
+}
+
+impl FromStr for LoadMethod {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "insert" => Ok(Self::Insert),
+            "copy_text" => Ok(Self::CopyText),
+            "copy_binary" => Ok(Self::CopyBinary),
+            other => {
+                bail!("load method must be one of: insert, copy_text, copy_binary (got {other:?})")
+            }
+        }
+    }
+}
+
+/// On-disk shape of the target column, which COPY has to produce directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnEncoding {
+    /// `jsonb`, or a domain over it.
+    Jsonb,
+    /// A composite with a single `jsonb` attribute, like EQL's `eql_v2_encrypted`.
+    Composite,
+}
+
+const JSONB_OID: u32 = 3802;
+const JSONB_VERSION: u8 = 1;
+const COPY_BINARY_HEADER: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+#[derive(Debug, Clone)]
+pub(crate) struct BatchWriter {
+    identifier: Identifier,
+    load_method: LoadMethod,
+    encoding: ColumnEncoding,
+    plaintext_identifier: Option<Identifier>,
+}
+
+impl BatchWriter {
+    pub(crate) async fn new(
+        pool: &PgPool,
+        identifier: Identifier,
+        load_method: LoadMethod,
+        plaintext_identifier: Option<Identifier>,
+    ) -> Result<Self> {
+        let encoding = match load_method {
+            LoadMethod::Insert => ColumnEncoding::Jsonb,
+            LoadMethod::CopyText | LoadMethod::CopyBinary => {
+                column_encoding(pool, &identifier).await?
+            }
+        };
+
+        Ok(Self {
+            identifier,
+            load_method,
+            encoding,
+            plaintext_identifier,
+        })
+    }
+
+    /// Whether batches also need their plaintexts, for the plaintext table.
+    pub(crate) fn writes_plaintext(&self) -> bool {
+        self.plaintext_identifier.is_some()
+    }
+
+    /// Write a batch of [`serialize`]d ciphertexts on `conn`, which may be inside a transaction.
+    ///
+    /// `plaintexts` must be given, in the same order as `ciphertexts`, when
+    /// [`Self::writes_plaintext`] is true.
+    pub(crate) async fn write(
+        &self,
+        conn: &mut PgConnection,
+        ciphertexts: Vec<Box<RawValue>>,
+        plaintexts: Option<Vec<Plaintext>>,
+    ) -> Result<()> {
+        let shadow = match (&self.plaintext_identifier, plaintexts) {
+            (Some(identifier), Some(plaintexts)) => {
+                let ids = self.reserve_ids(conn, ciphertexts.len()).await?;
+                Some((identifier, ids, plaintexts))
+            }
+            (Some(_), None) => bail!("plaintexts are required to write the plaintext table"),
+            (None, _) => None,
+        };
+        let ids = shadow.as_ref().map(|(_, ids, _)| ids.as_slice());
+
+        match self.load_method {
+            LoadMethod::Insert => {
+                QueryBuilder::new(format!(
+                    "INSERT INTO {} ({}) ",
+                    self.identifier.table(),
+                    self.columns(ids)
+                ))
+                .push_values(ciphertexts.into_iter().enumerate(), |mut b, (i, v)| {
+                    if let Some(ids) = ids {
+                        b.push_bind(ids[i]);
+                    }
+                    b.push_bind(Json(v));
+                })
+                .build()
+                .execute(&mut *conn)
+                .await?;
+            }
+            LoadMethod::CopyText => {
+                let data = self.encode_text(ids, &ciphertexts)?;
+                self.copy_in("text", ids, data, conn).await?;
+            }
+            LoadMethod::CopyBinary => {
+                let data = self.encode_binary(ids, &ciphertexts)?;
+                self.copy_in("binary", ids, data, conn).await?;
+            }
+        }
+
+        if let Some((identifier, ids, plaintexts)) = shadow {
+            write_plaintexts(conn, identifier, &ids, plaintexts).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Take the next `n` ids from the encrypted table's `id` sequence.
+    async fn reserve_ids(&self, conn: &mut PgConnection, n: usize) -> Result<Vec<i32>> {
+        let ids = sqlx::query_scalar(
+            "SELECT nextval(pg_get_serial_sequence($1, 'id'))::int
+             FROM generate_series(1, $2)",
+        )
+        .bind(self.identifier.table())
+        .bind(i32::try_from(n)?)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        Ok(ids)
+    }
+
+    fn columns(&self, ids: Option<&[i32]>) -> String {
+        match ids {
+            Some(_) => format!("id, {}", self.identifier.column()),
+            None => self.identifier.column().to_string(),
+        }
+    }
+
+    async fn copy_in(
+        &self,
+        format: &str,
+        ids: Option<&[i32]>,
+        data: Vec<u8>,
+        conn: &mut PgConnection,
+    ) -> Result<()> {
+        let statement = format!(
+            "COPY {} ({}) FROM STDIN WITH (FORMAT {format})",
+            self.identifier.table(),
+            self.columns(ids)
+        );
+
+        let mut copy = conn.copy_in_raw(&statement).await?;
+        copy.send(data).await?;
+        copy.finish().await?;
+
+        Ok(())
+    }
+
+    fn encode_text(&self, ids: Option<&[i32]>, ciphertexts: &[Box<RawValue>]) -> Result<Vec<u8>> {
+        let mut out = String::new();
+
+        for (i, ciphertext) in ciphertexts.iter().enumerate() {
+            if let Some(ids) = ids {
+                out.push_str(&format!("{}\t", ids[i]));
+            }
+
+            let json = ciphertext.get();
+            let field = match self.encoding {
+                ColumnEncoding::Jsonb => json.to_string(),
+                ColumnEncoding::Composite => {
+                    format!("(\"{}\")", json.replace('\\', "\\\\").replace('"', "\"\""))
+                }
+            };
+
+            for c in field.chars() {
+                match c {
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\r' => out.push_str("\\r"),
+                    '\t' => out.push_str("\\t"),
+                    c => out.push(c),
+                }
+            }
+            out.push('\n');
+        }
+
+        Ok(out.into_bytes())
+    }
+
+    fn encode_binary(&self, ids: Option<&[i32]>, ciphertexts: &[Box<RawValue>]) -> Result<Vec<u8>> {
+        let mut out = Vec::from(COPY_BINARY_HEADER);
+        // Flags and header extension length
+        out.extend_from_slice(&0i32.to_be_bytes());
+        out.extend_from_slice(&0i32.to_be_bytes());
+
+        for (i, ciphertext) in ciphertexts.iter().enumerate() {
+            let json = ciphertext.get().as_bytes();
+            let jsonb_len = i32::try_from(json.len() + 1).context("value too large for COPY")?;
+
+            // Field count, then the id when one is given
+            match ids {
+                Some(ids) => {
+                    out.extend_from_slice(&2i16.to_be_bytes());
+                    out.extend_from_slice(&4i32.to_be_bytes());
+                    out.extend_from_slice(&ids[i].to_be_bytes());
+                }
+                None => out.extend_from_slice(&1i16.to_be_bytes()),
+            }
+
+            match self.encoding {
+                ColumnEncoding::Jsonb => {
+                    out.extend_from_slice(&jsonb_len.to_be_bytes());
+                }
+                ColumnEncoding::Composite => {
+                    // Attribute count, attribute type OID and length, then the jsonb itself
+                    out.extend_from_slice(&(jsonb_len + 12).to_be_bytes());
+                    out.extend_from_slice(&1i32.to_be_bytes());
+                    out.extend_from_slice(&JSONB_OID.to_be_bytes());
+                    out.extend_from_slice(&jsonb_len.to_be_bytes());
+                }
+            }
+
+            out.push(JSONB_VERSION);
+            out.extend_from_slice(json);
+        }
+
+        // File trailer
+        out.extend_from_slice(&(-1i16).to_be_bytes());
+
+        Ok(out)
+    }
+}
+
+/// Serialize ciphertexts to JSON, ready to be written by [`BatchWriter::write`].
+pub(crate) fn serialize(ciphertexts: &[EqlCiphertext]) -> Result<Vec<Box<RawValue>>> {
+    ciphertexts
+        .iter()
+        .map(|ciphertext| Ok(serde_json::value::to_raw_value(ciphertext)?))
+        .collect()
+}
+
+async fn write_plaintexts(
+    conn: &mut PgConnection,
+    identifier: &Identifier,
+    ids: &[i32],
+    plaintexts: Vec<Plaintext>,
+) -> Result<()> {
+    let mut query = QueryBuilder::new(format!(
+        "INSERT INTO {} (id, {}) VALUES ",
+        identifier.table(),
+        identifier.column()
+    ));
+
+    for (i, (id, plaintext)) in ids.iter().zip(plaintexts).enumerate() {
+        if i > 0 {
+            query.push(", ");
+        }
+        query.push("(");
+        query.push_bind(*id);
+        query.push(", ");
+        push_plaintext(&mut query, &plaintext)?;
+        query.push(")");
+    }
+
+    query.build().execute(&mut *conn).await?;
+
+    Ok(())
+}
+
+fn push_plaintext(query: &mut QueryBuilder<'_, Postgres>, plaintext: &Plaintext) -> Result<()> {
+    match plaintext {
+        Plaintext::Boolean(v) => query.push_bind(*v),
+        Plaintext::SmallInt(v) => query.push_bind(*v),
+        Plaintext::Int(v) => query.push_bind(*v),
+        Plaintext::BigInt(v) => query.push_bind(*v),
+        Plaintext::Float(v) => query.push_bind(*v),
+        Plaintext::Utf8Str(v) => query.push_bind(v.clone()),
+        Plaintext::JsonB(v) => query.push_bind(v.clone().map(Json)),
+        other => bail!("can't write {} plaintexts", other.type_name()),
+    };
+
+    Ok(())
+}
+
+async fn column_encoding(pool: &PgPool, identifier: &Identifier) -> Result<ColumnEncoding> {
+    let (typtype, basetype, attributes): (String, String, Vec<String>) = sqlx::query_as(
+        "SELECT t.typtype::text,
+                format_type(COALESCE(NULLIF(t.typbasetype, 0), t.oid), NULL),
+                ARRAY(
+                    SELECT format_type(ca.atttypid, NULL)
+                    FROM pg_attribute ca
+                    WHERE ca.attrelid = t.typrelid AND ca.attnum > 0 AND NOT ca.attisdropped
+                )
+         FROM pg_attribute a
+         JOIN pg_type t ON t.oid = a.atttypid
+         WHERE a.attrelid = $1::regclass AND a.attname = $2",
+    )
+    .bind(identifier.table())
+    .bind(identifier.column())
+    .fetch_optional(pool)
+    .await?
+    .with_context(|| {
+        format!(
+            "column {}.{} not found",
+            identifier.table(),
+            identifier.column()
+        )
+    })?;
+
+    match typtype.as_str() {
+        "c" if attributes == ["jsonb"] => Ok(ColumnEncoding::Composite),
+        "b" | "d" if basetype == "jsonb" => Ok(ColumnEncoding::Jsonb),
+        _ => bail!(
+            "COPY can't load {}.{}: expected jsonb or a composite of one jsonb attribute",
+            identifier.table(),
+            identifier.column()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn writer(encoding: ColumnEncoding) -> BatchWriter {
+        BatchWriter {
+            identifier: Identifier::new("t", "value"),
+            load_method: LoadMethod::CopyText,
+            encoding,
+            plaintext_identifier: None,
+        }
+    }
+
+    fn raw(json: &str) -> Box<RawValue> {
+        RawValue::from_string(json.to_string()).unwrap()
+    }
+
+    fn be_i16(data: &[u8], at: usize) -> i16 {
+        i16::from_be_bytes(data[at..at + 2].try_into().unwrap())
+    }
+
+    fn be_i32(data: &[u8], at: usize) -> i32 {
+        i32::from_be_bytes(data[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn encode_text_escapes_tabs_backslashes_and_newlines() {
+        // Whitespace between the tokens, and a JSON-escaped backslash and tab
+        let values = [raw("{\"a\":\t\"x\\\\y\\tz\",\n\"b\":\r1}")];
+        let text = writer(ColumnEncoding::Jsonb)
+            .encode_text(None, &values)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            r#"{"a":\t"x\\\\y\\tz",\n"b":\r1}"#.to_string() + "\n"
+        );
+    }
+
+    #[test]
+    fn encode_text_writes_ids_and_one_row_per_value() {
+        let values = [raw("{\"a\":1}"), raw("{\"a\":2}")];
+        let text = writer(ColumnEncoding::Jsonb)
+            .encode_text(Some(&[7, 8]), &values)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            "7\t{\"a\":1}\n8\t{\"a\":2}\n"
+        );
+    }
+
+    #[test]
+    fn encode_text_quotes_composites() {
+        let values = [raw("{\"s\":\"x\\\\y\"}")];
+        let text = writer(ColumnEncoding::Composite)
+            .encode_text(None, &values)
+            .unwrap();
+
+        // Quotes are doubled and backslashes escaped for the composite, then
+        // the backslashes are escaped again for COPY
+        assert_eq!(
+            String::from_utf8(text).unwrap(),
+            r#"("{""s"":""x\\\\\\\\y""}")"#.to_string() + "\n"
+        );
+    }
+
+    #[test]
+    fn encode_binary_frames_jsonb_fields() {
+        let json = "{\"a\":1}";
+        let data = writer(ColumnEncoding::Jsonb)
+            .encode_binary(Some(&[42]), &[raw(json)])
+            .unwrap();
+
+        assert_eq!(&data[..11], COPY_BINARY_HEADER);
+        assert_eq!(be_i32(&data, 11), 0, "flags");
+        assert_eq!(be_i32(&data, 15), 0, "header extension length");
+
+        // Two fields: a 4-byte id, then the jsonb version byte and the JSON
+        assert_eq!(be_i16(&data, 19), 2);
+        assert_eq!(be_i32(&data, 21), 4);
+        assert_eq!(be_i32(&data, 25), 42);
+        assert_eq!(be_i32(&data, 29), json.len() as i32 + 1);
+        assert_eq!(data[33], JSONB_VERSION);
+        assert_eq!(&data[34..34 + json.len()], json.as_bytes());
+
+        assert_eq!(be_i16(&data, 34 + json.len()), -1, "trailer");
+        assert_eq!(data.len(), 36 + json.len());
+    }
+
+    #[test]
+    fn encode_binary_frames_composite_fields() {
+        let json = "{\"a\":1}";
+        let jsonb_len = json.len() as i32 + 1;
+        let data = writer(ColumnEncoding::Composite)
+            .encode_binary(None, &[raw(json), raw(json)])
+            .unwrap();
+
+        let mut at = 19;
+        for _ in 0..2 {
+            assert_eq!(be_i16(&data, at), 1, "field count");
+            // The composite holds its attribute count, the attribute's type
+            // OID and length, then the jsonb itself
+            assert_eq!(be_i32(&data, at + 2), jsonb_len + 12);
+            assert_eq!(be_i32(&data, at + 6), 1);
+            assert_eq!(be_i32(&data, at + 10), JSONB_OID as i32);
+            assert_eq!(be_i32(&data, at + 14), jsonb_len);
+            assert_eq!(data[at + 18], JSONB_VERSION);
+            assert_eq!(&data[at + 19..at + 19 + json.len()], json.as_bytes());
+            at += 19 + json.len();
+        }
+
+        assert_eq!(be_i16(&data, at), -1, "trailer");
+        assert_eq!(data.len(), at + 2);
+    }
+}
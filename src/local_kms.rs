@@ -0,0 +1,140 @@
+//! An in-process stand-in for the ZeroKMS key service
+//!
+//! Implements the subset of the ZeroKMS HTTP API used by `ScopedCipher`
+//! (`load-keyset`, `generate-data-key`, `retrieve-data-key` and
+//! `retrieve-data-key-fallible`) on a loopback port. Key material is derived
+//! from a fixed root key and the IV of each data key, so keys generated in one
+//! process can be retrieved by any other process using the local backend.
+//!
+//! The keys in this module are public test keys and provide no security.
+
+use anyhow::Result;
+use axum::{routing::post, Json, Router};
+use cipherstash_client::zerokms::{Keyset, ViturKeyMaterial};
+use tokio::net::TcpListener;
+use url::Url;
+use uuid::{uuid, Uuid};
+use zerokms_protocol::{
+    GenerateKeyRequest, GenerateKeyResponse, GeneratedKey, LoadKeysetRequest, LoadKeysetResponse,
+    RetrieveKeyRequest, RetrieveKeyRequestFallible, RetrieveKeyResponse,
+    RetrieveKeyResponseFallible, RetrievedKey,
+};
+
+pub const CLIENT_ID: Uuid = uuid!("00000000-0000-4000-8000-00000000c11e");
+
+/// CBOR-encoded client keyset, as it would appear in `CS_CLIENT_KEY`.
+pub const CLIENT_KEY_HEX: &str = "a4627031a16b7065726d75746174696f6e900c020d0004060107030908050e0b0f0a6770325f66726f6da16b7065726d75746174696f6e9001080b02090e07060f0c05030d040a006570325f746fa16b7065726d75746174696f6e900e0208030b0d06050f000109070c040a627033a16b7065726d75746174696f6e9821041112181d0f0903181e140206181a131819170a05181f070c08181b181c0d0b1516182018180100100e";
+
+const KEYSET_ID: Uuid = uuid!("00000000-0000-4000-8000-0000000005e7");
+
+const ROOT_KEY: [u8; 32] = *b"ore-benches local zerokms root!!";
+
+/// The client reencrypts key material in 33 blocks of 16 bytes.
+const KEY_MATERIAL_LEN: usize = 33 * 16;
+
+const TAG_LEN: usize = 16;
+
+/// Start the key service on a random loopback port and return its base URL.
+///
+/// The service runs until the current tokio runtime shuts down.
+pub async fn spawn() -> Result<Url> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+
+    let app = Router::new()
+        .route("/load-keyset", post(load_keyset))
+        .route("/generate-data-key", post(generate_data_key))
+        .route("/retrieve-data-key", post(retrieve_data_key))
+        .route(
+            "/retrieve-data-key-fallible",
+            post(retrieve_data_key_fallible),
+        );
+
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    Ok(Url::parse(&format!("http://{addr}"))?)
+}
+
+fn derive(label: &[u8], parts: &[&[u8]], out: &mut [u8]) {
+    let mut hasher = blake3::Hasher::new_keyed(&ROOT_KEY);
+    hasher.update(label);
+    for part in parts {
+        hasher.update(&(part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize_xof().fill(out);
+}
+
+fn key_material(iv: &[u8]) -> ViturKeyMaterial {
+    let mut material = vec![0; KEY_MATERIAL_LEN];
+    derive(b"data-key", &[iv], &mut material);
+    material.into()
+}
+
+fn tag(iv: &[u8], descriptor: &str) -> Vec<u8> {
+    let mut tag = vec![0; TAG_LEN];
+    derive(b"tag", &[iv, descriptor.as_bytes()], &mut tag);
+    tag
+}
+
+async fn load_keyset(Json(_): Json<LoadKeysetRequest>) -> Json<LoadKeysetResponse> {
+    let mut material = vec![0; KEY_MATERIAL_LEN];
+    derive(b"index-key", &[KEYSET_ID.as_bytes()], &mut material);
+
+    Json(LoadKeysetResponse {
+        partial_index_key: RetrievedKey {
+            key_material: material.into(),
+        },
+        keyset: Keyset {
+            id: KEYSET_ID,
+            name: "local".to_string(),
+            description: "ore-benches local test keyset".to_string(),
+            is_disabled: false,
+        },
+    })
+}
+
+async fn generate_data_key(
+    Json(req): Json<GenerateKeyRequest<'static>>,
+) -> Json<GenerateKeyResponse> {
+    let keys = req
+        .keys
+        .iter()
+        .map(|spec| GeneratedKey {
+            key_material: key_material(spec.iv.as_ref()),
+            tag: tag(spec.iv.as_ref(), &spec.descriptor),
+        })
+        .collect();
+
+    Json(GenerateKeyResponse { keys })
+}
+
+async fn retrieve_data_key(
+    Json(req): Json<RetrieveKeyRequest<'static>>,
+) -> Json<RetrieveKeyResponse> {
+    let keys = req
+        .keys
+        .iter()
+        .map(|spec| RetrievedKey {
+            key_material: key_material(spec.iv.as_ref()),
+        })
+        .collect();
+
+    Json(RetrieveKeyResponse { keys })
+}
+
+async fn retrieve_data_key_fallible(
+    Json(req): Json<RetrieveKeyRequestFallible<'static>>,
+) -> Json<RetrieveKeyResponseFallible> {
+    let keys = req
+        .keys
+        .iter()
+        .map(|spec| {
+            Ok(RetrievedKey {
+                key_material: key_material(spec.iv.as_ref()),
+            })
+        })
+        .collect();
+
+    Json(RetrieveKeyResponseFallible { keys })
+}
@@ -0,0 +1,917 @@
+//! Mixed read/write workloads on the encrypted tables
+//!
+//! Ingest runs on a table nobody is reading, and the query benches run on a
+//! table nobody is writing. A mixed workload does both at once, in the style of
+//! YCSB: each client picks an operation by ratio (a point lookup, a range scan,
+//! an update of an existing row or an insert of a new one), encrypts its values
+//! and runs it, until the time is up. Writes are encrypted with
+//! `EqlOperation::Store` and have to maintain every index on the column, so the
+//! results show how the ORE operator class, hash and GIN indexes hold up under
+//! concurrent writes, and how much the writers slow the readers down.
+//!
+//! Latencies include encrypting the operation's values, as an application has
+//! to do that for every operation too.
+//!
+//! Lookups and scans start from values sampled from the table when the
+//! workload is loaded. They're decrypted from the encrypted table, so the
+//! plaintext table doesn't have to be populated. Updates give rows new
+//! generated values, so a later lookup of a value that was overwritten finds
+//! nothing, like a read of a deleted key.
+//!
+//! The tables are shared with the other benchmarks, so the original value of
+//! every row an update overwrites is kept, outside the timed part, and written
+//! back by [`MixedWorkload::restore_updated`].
+
+use crate::{
+    workload::parse_weights, Cipher, EncryptedQuery, EncryptedQueryBuilder, Latencies,
+    LatencySummary, Param, ParamOp, QueryParam,
+};
+use anyhow::{bail, Context, Result};
+use cipherstash_client::{
+    eql::Identifier,
+    schema::{
+        column::{Index, IndexType},
+        ColumnConfig, ColumnType,
+    },
+};
+use fake::{
+    faker::name::raw::Name,
+    locales::EN,
+    rand::{rngs::StdRng, Rng, SeedableRng},
+    Fake, Faker,
+};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{postgres::PgArguments, types::Json, PgPool};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+
+/// Operation ratios used when none are given.
+pub const DEFAULT_OPERATION_RATIOS: &str = "read=50,scan=10,update=30,insert=10";
+
+/// A table a mixed workload runs against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixedTable {
+    /// `integer_encrypted_*`, with an ORE index. Lookups and scans use ORE terms.
+    Integer,
+    /// `string_encrypted_*`, with unique and match indexes. Lookups use the
+    /// unique (HMAC) index and scans are free text matches on a last name,
+    /// since the column has no ORE index to scan a range with.
+    String,
+}
+
+impl MixedTable {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Integer => "integer",
+            Self::String => "string",
+        }
+    }
+
+    pub fn table_name(&self, target_rows: &str) -> String {
+        format!("{}_encrypted_{target_rows}", self.as_str())
+    }
+
+    pub fn plaintext_table_name(&self, target_rows: &str) -> String {
+        format!("{}_plaintext_{target_rows}", self.as_str())
+    }
+
+    /// The column config the table was encrypted with by its ingest binary.
+    pub fn column_config(&self) -> ColumnConfig {
+        match self {
+            Self::Integer => ColumnConfig::build("value")
+                .casts_as(ColumnType::Int)
+                .add_index(Index::new_ore()),
+            Self::String => ColumnConfig::build("value")
+                .casts_as(ColumnType::Utf8Str)
+                .add_index(Index::new_unique())
+                .add_index(Index::new_match()),
+        }
+    }
+
+    /// The index point lookups are encrypted for.
+    pub(crate) fn lookup_index(&self) -> IndexType {
+        match self {
+            Self::Integer => Index::new_ore().index_type,
+            Self::String => Index::new_unique().index_type,
+        }
+    }
+
+    /// The index range scans are encrypted for.
+    fn scan_index(&self) -> IndexType {
+        match self {
+            Self::Integer => Index::new_ore().index_type,
+            Self::String => Index::new_match().index_type,
+        }
+    }
+
+    /// A new value, generated like the table's ingest binary does.
+    pub(crate) fn generate(&self, rng: &mut StdRng) -> Param {
+        match self {
+            Self::Integer => Param::Int(Faker.fake_with_rng(rng)),
+            Self::String => Param::Text(Name(EN).fake_with_rng(rng)),
+        }
+    }
+
+    /// The value a scan starting from `key` searches for.
+    fn scan_param(&self, key: &Param) -> Param {
+        match (self, key) {
+            (Self::String, Param::Text(name)) => {
+                Param::Text(name.split_whitespace().last().unwrap_or(name).to_string())
+            }
+            (_, key) => key.clone(),
+        }
+    }
+}
+
+impl FromStr for MixedTable {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "integer" => Ok(Self::Integer),
+            "string" => Ok(Self::String),
+            other => bail!("TABLE must be one of: integer, string (got {other:?})"),
+        }
+    }
+}
+
+impl fmt::Display for MixedTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One kind of operation in a mixed workload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Look up a row by an encrypted value (`value = $1 LIMIT 1`).
+    Read,
+    /// Fetch up to the scan length of rows from an encrypted value on.
+    Scan,
+    /// Give an existing row, picked by id, a new encrypted value.
+    Update,
+    /// Insert a row with a new encrypted value.
+    Insert,
+}
+
+impl Operation {
+    pub const ALL: [Self; 4] = [Self::Read, Self::Scan, Self::Update, Self::Insert];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Scan => "scan",
+            Self::Update => "update",
+            Self::Insert => "insert",
+        }
+    }
+}
+
+impl FromStr for Operation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "read" => Ok(Self::Read),
+            "scan" => Ok(Self::Scan),
+            "update" => Ok(Self::Update),
+            "insert" => Ok(Self::Insert),
+            other => bail!("operation must be one of: read, scan, update, insert (got {other:?})"),
+        }
+    }
+}
+
+/// How often each operation is picked, relative to the others.
+#[derive(Debug, Clone)]
+pub struct OperationMix {
+    /// Operations with a ratio above 0, in [`Operation::ALL`] order.
+    pub ratios: Vec<(Operation, u32)>,
+    /// Running total of the ratios, for picking an operation.
+    cumulative: Vec<u32>,
+}
+
+impl OperationMix {
+    /// Parse `operation=ratio` pairs such as `read=50,update=50`. Operations
+    /// that aren't listed are left out.
+    pub fn parse(ratios: &str) -> Result<Self> {
+        let parsed = parse_weights(ratios)?
+            .into_iter()
+            .map(|(name, ratio)| Ok((name.parse::<Operation>()?, ratio)))
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        let ratios: Vec<_> = Operation::ALL
+            .into_iter()
+            .filter_map(|op| {
+                parsed
+                    .get(&op)
+                    .copied()
+                    .filter(|&ratio| ratio > 0)
+                    .map(|ratio| (op, ratio))
+            })
+            .collect();
+        if ratios.is_empty() {
+            bail!("the workload has no operations with a ratio above 0");
+        }
+
+        let cumulative = ratios
+            .iter()
+            .scan(0, |total, (_, ratio)| {
+                *total += ratio;
+                Some(*total)
+            })
+            .collect();
+        Ok(Self { ratios, cumulative })
+    }
+
+    /// A random operation, chosen by ratio.
+    pub fn pick(&self, rng: &mut impl Rng) -> Operation {
+        let total = *self.cumulative.last().unwrap();
+        let n = rng.random_range(0..total);
+        self.ratios[self.cumulative.partition_point(|&c| c <= n)].0
+    }
+
+    /// A name for the mix to use in file names, such as `read50_update50`.
+    pub fn label(&self) -> String {
+        self.ratios
+            .iter()
+            .map(|(op, ratio)| format!("{}{ratio}", op.as_str()))
+            .collect::<Vec<_>>()
+            .join("_")
+    }
+}
+
+impl fmt::Display for OperationMix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pairs: Vec<_> = self
+            .ratios
+            .iter()
+            .map(|(op, ratio)| format!("{}={ratio}", op.as_str()))
+            .collect();
+        f.write_str(&pairs.join(","))
+    }
+}
+
+/// An encrypted table loaded for a mixed workload.
+pub struct MixedWorkload {
+    pub table: MixedTable,
+    pub table_name: String,
+    pub plaintext_table_name: String,
+    pub column_config: ColumnConfig,
+    /// Values sampled from the table, for lookups and scans to start from.
+    pub keys: Vec<Param>,
+    /// Ids of the rows in the table when it was loaded, for updates to pick from.
+    pub ids: RangeInclusive<i32>,
+    pub(crate) cipher: Arc<Cipher>,
+    /// The values rows had before their first update, by id.
+    originals: Mutex<HashMap<i32, Original>>,
+}
+
+/// A row's values before it was first updated.
+struct Original {
+    ciphertext: Json<Value>,
+    /// The plaintext table's value, when writes are mirrored to it.
+    plaintext: Option<Param>,
+}
+
+impl MixedWorkload {
+    /// Sample `sample` values from the table of `target_rows` rows, in an
+    /// order fixed by `seed`, and find the range of its ids.
+    pub async fn load(
+        table: MixedTable,
+        target_rows: &str,
+        db: &PgPool,
+        cipher: Arc<Cipher>,
+        sample: usize,
+        seed: u64,
+    ) -> Result<Self> {
+        let table_name = table.table_name(target_rows);
+        let column_config = table.column_config();
+        // Lookups and scans pick from the sampled values
+        if sample == 0 {
+            bail!("at least one value must be sampled from {table_name}");
+        }
+
+        let (min, max): (Option<i32>, Option<i32>) =
+            sqlx::query_as(&format!("SELECT min(id), max(id) FROM {table_name}"))
+                .fetch_one(db)
+                .await
+                .with_context(|| format!("failed to read the ids of {table_name}"))?;
+        let (Some(min), Some(max)) = (min, max) else {
+            bail!("{table_name} is empty, prepare it before running a mixed workload");
+        };
+
+        let query = EncryptedQueryBuilder::new(
+            column_config.clone(),
+            Identifier::new(&table_name, "value"),
+        )
+        .statement(format!(
+            "SELECT id,value::jsonb FROM {table_name} ORDER BY md5(id::text || $1) LIMIT $2"
+        ))
+        .param(QueryParam::plain(seed.to_string()))
+        .param(QueryParam::plain(sample as i64))
+        .build(Arc::clone(&cipher))
+        .await?;
+        let keys: Vec<Param> = match table {
+            MixedTable::Integer => query
+                .execute_and_decrypt::<i32>(db)
+                .await?
+                .into_iter()
+                .map(Param::from)
+                .collect(),
+            MixedTable::String => query
+                .execute_and_decrypt::<String>(db)
+                .await?
+                .into_iter()
+                .map(Param::from)
+                .collect(),
+        };
+        if keys.is_empty() {
+            bail!("no values could be sampled from {table_name}");
+        }
+
+        Ok(Self {
+            table,
+            plaintext_table_name: table.plaintext_table_name(target_rows),
+            table_name,
+            column_config,
+            keys,
+            ids: min..=max,
+            cipher,
+            originals: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub(crate) fn builder(&self) -> EncryptedQueryBuilder {
+        EncryptedQueryBuilder::new(
+            self.column_config.clone(),
+            Identifier::new(&self.table_name, "value"),
+        )
+    }
+
+    /// Encrypt and run one `op`, where an update overwrites the row `target`.
+    /// Returns the number of rows read, or for writes the id and plaintext of
+    /// the row written.
+    async fn run(
+        &self,
+        op: Operation,
+        target: Option<i32>,
+        rng: &mut StdRng,
+        db: &PgPool,
+        options: &MixedOptions,
+    ) -> Result<Outcome> {
+        let table = &self.table_name;
+        let read = |statement: String, index_type: IndexType, value: Param| {
+            self.builder()
+                .index_type(index_type)
+                .statement(statement)
+                .param(QueryParam::encrypted(value))
+                .build(Arc::clone(&self.cipher))
+        };
+
+        match op {
+            Operation::Read => {
+                let key = self.keys[rng.random_range(0..self.keys.len())].clone();
+                let statement =
+                    format!("SELECT id,value::jsonb FROM {table} WHERE value = $1 LIMIT 1");
+                let query = read(statement, self.table.lookup_index(), key).await?;
+                self.fetch(&query, db, options.decrypt).await
+            }
+            Operation::Scan => {
+                let key = &self.keys[rng.random_range(0..self.keys.len())];
+                let statement = match self.table {
+                    MixedTable::Integer => format!(
+                        "SELECT id,value::jsonb FROM {table} WHERE value >= $1 ORDER BY value LIMIT {}",
+                        options.scan_length
+                    ),
+                    MixedTable::String => format!(
+                        "SELECT id,value::jsonb FROM {table} WHERE value LIKE $1 LIMIT {}",
+                        options.scan_length
+                    ),
+                };
+                let param = self.table.scan_param(key);
+                let query = read(statement, self.table.scan_index(), param).await?;
+                self.fetch(&query, db, options.decrypt).await
+            }
+            Operation::Update => {
+                let id = target.context("updates need a row to overwrite")?;
+                let value = self.table.generate(rng);
+                let query = self
+                    .builder()
+                    .index_type(self.table.lookup_index())
+                    .statement(format!("UPDATE {table} SET value = $1 WHERE id = $2"))
+                    .param(QueryParam::encrypted(value.clone()).op(ParamOp::Store))
+                    .param(QueryParam::plain(id))
+                    .build(Arc::clone(&self.cipher))
+                    .await?;
+                sqlx::query_with(&query.statement, query.arguments()?)
+                    .execute(db)
+                    .await?;
+                Ok(Outcome::Written(id, value))
+            }
+            Operation::Insert => {
+                let value = self.table.generate(rng);
+                let query = self
+                    .builder()
+                    .index_type(self.table.lookup_index())
+                    .statement(format!(
+                        "INSERT INTO {table} (value) VALUES ($1) RETURNING id"
+                    ))
+                    .param(QueryParam::encrypted(value.clone()).op(ParamOp::Store))
+                    .build(Arc::clone(&self.cipher))
+                    .await?;
+                let id: i32 = sqlx::query_scalar_with(&query.statement, query.arguments()?)
+                    .fetch_one(db)
+                    .await?;
+                Ok(Outcome::Written(id, value))
+            }
+        }
+    }
+
+    async fn fetch(&self, query: &EncryptedQuery, db: &PgPool, decrypt: bool) -> Result<Outcome> {
+        let rows = match (decrypt, self.table) {
+            (false, _) => query.execute(db).await?.len(),
+            (true, MixedTable::Integer) => query.execute_and_decrypt::<i32>(db).await?.len(),
+            (true, MixedTable::String) => query.execute_and_decrypt::<String>(db).await?.len(),
+        };
+        Ok(Outcome::Read(rows))
+    }
+
+    /// Apply a write of `op` to the plaintext table too, so it still mirrors
+    /// the encrypted one for baselines and verification. Fails if no plaintext
+    /// row was written, such as an update of a row the plaintext table lacks.
+    async fn mirror(&self, op: Operation, id: i32, value: &Param, db: &PgPool) -> Result<()> {
+        let table = &self.plaintext_table_name;
+        let statement = match op {
+            Operation::Update => format!("UPDATE {table} SET value = $2 WHERE id = $1"),
+            Operation::Insert => format!("INSERT INTO {table} (id, value) VALUES ($1, $2)"),
+            Operation::Read | Operation::Scan => return Ok(()),
+        };
+        let mut args = PgArguments::default();
+        Param::Int(id).add_to(&mut args)?;
+        value.add_to(&mut args)?;
+        let written = sqlx::query_with(&statement, args)
+            .execute(db)
+            .await
+            .with_context(|| format!("failed to mirror {} to {table}", op.as_str()))?
+            .rows_affected();
+        if written == 0 {
+            bail!("{table} has no row {id} to mirror {} to", op.as_str());
+        }
+        Ok(())
+    }
+
+    /// Keep the values of row `id` before its first update, for
+    /// [`Self::restore_updated`] to write back.
+    async fn save_original(&self, id: i32, db: &PgPool, write_plaintext: bool) -> Result<()> {
+        if self.originals.lock().unwrap().contains_key(&id) {
+            return Ok(());
+        }
+
+        let ciphertext: Option<Json<Value>> = sqlx::query_scalar(&format!(
+            "SELECT value::jsonb FROM {} WHERE id = $1",
+            self.table_name
+        ))
+        .bind(id)
+        .fetch_optional(db)
+        .await
+        .with_context(|| format!("failed to read row {id} of {}", self.table_name))?;
+        // An update of a missing row changes nothing
+        let Some(ciphertext) = ciphertext else {
+            return Ok(());
+        };
+        let plaintext = match write_plaintext {
+            false => None,
+            true => {
+                let statement = format!(
+                    "SELECT value FROM {} WHERE id = $1",
+                    self.plaintext_table_name
+                );
+                match self.table {
+                    MixedTable::Integer => sqlx::query_scalar(&statement)
+                        .bind(id)
+                        .fetch_optional(db)
+                        .await?
+                        .map(Param::Int),
+                    MixedTable::String => sqlx::query_scalar(&statement)
+                        .bind(id)
+                        .fetch_optional(db)
+                        .await?
+                        .map(Param::Text),
+                }
+            }
+        };
+
+        // A concurrent update may have saved the row first, with the value it read
+        // before overwriting it
+        self.originals
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_insert(Original {
+                ciphertext,
+                plaintext,
+            });
+        Ok(())
+    }
+
+    /// Write back the original values of every row updated since the workload
+    /// was loaded or last restored, to both tables. Returns the number of rows
+    /// restored.
+    pub async fn restore_updated(&self, db: &PgPool) -> Result<u64> {
+        let originals = std::mem::take(&mut *self.originals.lock().unwrap());
+        let mut tx = db.begin().await?;
+        for (id, original) in &originals {
+            sqlx::query(&format!(
+                "UPDATE {} SET value = $1 WHERE id = $2",
+                self.table_name
+            ))
+            .bind(&original.ciphertext)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("failed to restore row {id} of {}", self.table_name))?;
+
+            if let Some(plaintext) = &original.plaintext {
+                let mut args = PgArguments::default();
+                plaintext.add_to(&mut args)?;
+                Param::Int(*id).add_to(&mut args)?;
+                sqlx::query_with(
+                    &format!(
+                        "UPDATE {} SET value = $1 WHERE id = $2",
+                        self.plaintext_table_name
+                    ),
+                    args,
+                )
+                .execute(&mut *tx)
+                .await
+                .with_context(|| {
+                    format!(
+                        "failed to restore row {id} of {}",
+                        self.plaintext_table_name
+                    )
+                })?;
+            }
+        }
+        tx.commit().await?;
+
+        Ok(originals.len() as u64)
+    }
+
+    /// Delete the rows inserted since the workload was loaded, from both
+    /// tables, so the table is back to its target size. Returns the number of
+    /// rows deleted.
+    pub async fn delete_inserted(&self, db: &PgPool, write_plaintext: bool) -> Result<u64> {
+        let max = *self.ids.end();
+        let deleted = sqlx::query(&format!("DELETE FROM {} WHERE id > $1", self.table_name))
+            .bind(max)
+            .execute(db)
+            .await?
+            .rows_affected();
+        if write_plaintext {
+            sqlx::query(&format!(
+                "DELETE FROM {} WHERE id > $1",
+                self.plaintext_table_name
+            ))
+            .bind(max)
+            .execute(db)
+            .await?;
+        }
+        Ok(deleted)
+    }
+}
+
+/// The result of one operation.
+enum Outcome {
+    /// Rows returned by a lookup or scan.
+    Read(usize),
+    /// The id and plaintext of the row an update or insert wrote.
+    Written(i32, Param),
+}
+
+/// How a mixed workload is run.
+#[derive(Debug, Clone)]
+pub struct MixedOptions {
+    pub mix: OperationMix,
+    pub clients: usize,
+    /// Time spent running operations before any are recorded.
+    pub warmup: Duration,
+    pub duration: Duration,
+    /// Decrypt the rows returned by lookups and scans.
+    pub decrypt: bool,
+    /// Mirror writes to the plaintext table, outside the timed part.
+    pub write_plaintext: bool,
+    /// Most rows a scan returns.
+    pub scan_length: usize,
+    pub seed: u64,
+}
+
+/// Operations completed by one client, in [`OperationMix::ratios`] order.
+struct ClientResult {
+    latencies: Vec<Latencies>,
+    /// Operations that finished during the measured time.
+    completed: Vec<u64>,
+    errors: Vec<u64>,
+    rows: Vec<u64>,
+    mirror_errors: Vec<u64>,
+}
+
+/// Run `options.clients` clients against the workload's table, each running
+/// one operation after another until the time is up.
+///
+/// Operations that start during the warmup aren't recorded, and throughput
+/// counts the operations that finished within `options.duration`. Failed
+/// operations are counted but don't stop the run. Writes that couldn't be mirrored to the
+/// plaintext table are counted separately, warmup included, since any of them
+/// leaves the plaintext table out of step with the encrypted one.
+pub async fn run_mixed_workload(
+    workload: Arc<MixedWorkload>,
+    db: &PgPool,
+    options: &MixedOptions,
+) -> Result<Vec<OperationLoad>> {
+    let measure_from = Instant::now() + options.warmup;
+    let end = measure_from + options.duration;
+    let n = options.mix.ratios.len();
+
+    let mut clients = JoinSet::new();
+    for client in 0..options.clients {
+        let workload = Arc::clone(&workload);
+        let db = db.clone();
+        let options = options.clone();
+        let mut rng = StdRng::seed_from_u64(options.seed.wrapping_add(client as u64));
+
+        clients.spawn(async move {
+            let mut result = ClientResult {
+                latencies: vec![Latencies::new(); n],
+                completed: vec![0; n],
+                errors: vec![0; n],
+                rows: vec![0; n],
+                mirror_errors: vec![0; n],
+            };
+
+            loop {
+                let started = Instant::now();
+                if started >= end {
+                    break;
+                }
+                let op = options.mix.pick(&mut rng);
+                let i = options
+                    .mix
+                    .ratios
+                    .iter()
+                    .position(|(o, _)| *o == op)
+                    .expect("picked operations are in the mix");
+                let mut target = None;
+                if op == Operation::Update {
+                    let id = rng.random_range(workload.ids.clone());
+                    let saved = workload
+                        .save_original(id, &db, options.write_plaintext)
+                        .await;
+                    if saved.is_err() {
+                        // The row couldn't be restored, so don't overwrite it
+                        if started >= measure_from {
+                            result.errors[i] += 1;
+                        }
+                        continue;
+                    }
+                    target = Some(id);
+                }
+
+                let started = Instant::now();
+                let outcome = workload.run(op, target, &mut rng, &db, &options).await;
+                let elapsed = started.elapsed();
+                let finished = started + elapsed;
+                if outcome.is_ok() && finished >= measure_from && finished < end {
+                    result.completed[i] += 1;
+                }
+
+                if let Ok(Outcome::Written(id, value)) = &outcome {
+                    if options.write_plaintext
+                        && workload.mirror(op, *id, value, &db).await.is_err()
+                    {
+                        result.mirror_errors[i] += 1;
+                    }
+                }
+                if started < measure_from {
+                    continue;
+                }
+                match outcome {
+                    Ok(outcome) => {
+                        result.latencies[i].record(elapsed);
+                        result.rows[i] += match outcome {
+                            Outcome::Read(rows) => rows as u64,
+                            Outcome::Written(..) => 1,
+                        };
+                    }
+                    Err(_) => result.errors[i] += 1,
+                }
+            }
+            result
+        });
+    }
+
+    let mut latencies = vec![Latencies::new(); n];
+    let mut completed = vec![0; n];
+    let mut errors = vec![0; n];
+    let mut rows = vec![0; n];
+    let mut mirror_errors = vec![0; n];
+    while let Some(result) = clients.join_next().await {
+        let result = result.context("mixed workload client panicked")?;
+        for (total, l) in latencies.iter_mut().zip(&result.latencies) {
+            total.add(l);
+        }
+        for (total, c) in completed.iter_mut().zip(&result.completed) {
+            *total += c;
+        }
+        for (total, e) in errors.iter_mut().zip(&result.errors) {
+            *total += e;
+        }
+        for (total, r) in rows.iter_mut().zip(&result.rows) {
+            *total += r;
+        }
+        for (total, e) in mirror_errors.iter_mut().zip(&result.mirror_errors) {
+            *total += e;
+        }
+    }
+
+    let seconds = options.duration.as_secs_f64();
+    Ok(options
+        .mix
+        .ratios
+        .iter()
+        .zip(latencies.iter().zip(errors.into_iter().zip(rows)))
+        .zip(completed.into_iter().zip(mirror_errors))
+        .map(
+            |(((op, ratio), (latencies, (errors, rows))), (completed, mirror_errors))| {
+                OperationLoad {
+                    operation: op.as_str().to_string(),
+                    ratio: *ratio,
+                    ops_per_sec: completed as f64 / seconds,
+                    errors,
+                    rows_per_op: match latencies.len() {
+                        0 => 0.0,
+                        count => rows as f64 / count as f64,
+                    },
+                    mirror_errors,
+                    latency: latencies.summary(),
+                }
+            },
+        )
+        .collect())
+}
+
+/// Throughput and latency of one operation in a mixed workload.
+#[derive(Debug, Serialize)]
+pub struct OperationLoad {
+    pub operation: String,
+    pub ratio: u32,
+    pub ops_per_sec: f64,
+    pub errors: u64,
+    /// Mean rows returned (reads) or written (writes) per operation.
+    pub rows_per_op: f64,
+    /// Writes that failed to reach the plaintext table, or found no row there.
+    pub mirror_errors: u64,
+    pub latency: LatencySummary,
+}
+
+/// The results of a mixed workload at one level of concurrency.
+#[derive(Debug, Serialize)]
+pub struct MixedReport {
+    pub table: String,
+    pub target_rows: String,
+    pub mix: String,
+    pub clients: usize,
+    pub duration_seconds: f64,
+    pub decrypt: bool,
+    pub scan_length: usize,
+    pub ops_per_sec: f64,
+    pub errors: u64,
+    pub mirror_errors: u64,
+    pub operations: Vec<OperationLoad>,
+    #[serde(skip)]
+    label: String,
+}
+
+impl MixedReport {
+    pub fn new(
+        workload: &MixedWorkload,
+        target_rows: impl Into<String>,
+        options: &MixedOptions,
+        operations: Vec<OperationLoad>,
+    ) -> Self {
+        Self {
+            table: workload.table.as_str().to_string(),
+            target_rows: target_rows.into(),
+            mix: options.mix.to_string(),
+            clients: options.clients,
+            duration_seconds: options.duration.as_secs_f64(),
+            decrypt: options.decrypt,
+            scan_length: options.scan_length,
+            ops_per_sec: operations.iter().map(|o| o.ops_per_sec).sum(),
+            errors: operations.iter().map(|o| o.errors).sum(),
+            mirror_errors: operations.iter().map(|o| o.mirror_errors).sum(),
+            operations,
+            label: options.mix.label(),
+        }
+    }
+
+    /// Print a summary and write the report to
+    /// `results/load/mixed_{table}{_decrypt}_{mix}_clients_{c}_rows_{n}.json`.
+    pub fn write(&self) -> Result<PathBuf> {
+        println!(
+            "{} clients: {:.1} operations/sec, {} errors",
+            self.clients, self.ops_per_sec, self.errors
+        );
+        for o in &self.operations {
+            println!(
+                "  {}: {:.1} operations/sec, p50 {:.2}ms, p95 {:.2}ms, p99 {:.2}ms, p99.9 {:.2}ms, max {:.2}ms, {} errors",
+                o.operation,
+                o.ops_per_sec,
+                o.latency.p50_ms,
+                o.latency.p95_ms,
+                o.latency.p99_ms,
+                o.latency.p999_ms,
+                o.latency.max_ms,
+                o.errors,
+            );
+        }
+        if self.mirror_errors > 0 {
+            println!(
+                "Warning: {} writes weren't mirrored to {}_plaintext_{}, so it no longer matches the encrypted table and VERIFY will report mismatches",
+                self.mirror_errors, self.table, self.target_rows
+            );
+        }
+
+        let decrypt = if self.decrypt { "_decrypt" } else { "" };
+        let path = PathBuf::from(format!(
+            "results/load/mixed_{}{decrypt}_{}_clients_{}_rows_{}.json",
+            self.table, self.label, self.clients, self.target_rows
+        ));
+        fs::create_dir_all("results/load")?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn operation_mix_keeps_operations_in_order() {
+        let mix = OperationMix::parse("insert=10,READ=50,update=40").unwrap();
+
+        assert_eq!(mix.to_string(), "read=50,update=40,insert=10");
+        assert_eq!(mix.label(), "read50_update40_insert10");
+    }
+
+    #[test]
+    fn operation_mix_leaves_out_zero_ratios() {
+        let mix = OperationMix::parse("read=1,scan=0,update=0").unwrap();
+
+        assert_eq!(mix.to_string(), "read=1");
+        let mut rng = StdRng::seed_from_u64(0);
+        assert!((0..100).all(|_| mix.pick(&mut rng) == Operation::Read));
+    }
+
+    #[test]
+    fn operation_mix_rejects_unknown_operations() {
+        let err = OperationMix::parse("read=50,delete=50").unwrap_err();
+        assert!(err.to_string().contains("\"delete\""), "{err}");
+    }
+
+    #[test]
+    fn operation_mix_rejects_negative_ratios() {
+        assert!(OperationMix::parse("read=50,update=-10").is_err());
+    }
+
+    #[test]
+    fn operation_mix_rejects_a_mix_without_operations() {
+        for ratios in ["read=0,update=0", ""] {
+            let err = OperationMix::parse(ratios).unwrap_err();
+            assert!(
+                err.to_string().contains("no operations"),
+                "{ratios:?}: {err}"
+            );
+        }
+    }
+
+    #[test]
+    fn operation_mix_picks_every_operation() {
+        let mix = OperationMix::parse(DEFAULT_OPERATION_RATIOS).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        let picked: HashSet<Operation> = (0..1000).map(|_| mix.pick(&mut rng)).collect();
+
+        assert_eq!(picked.len(), Operation::ALL.len());
+    }
+}
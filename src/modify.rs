@@ -0,0 +1,326 @@
+//! Benchmarks for updating and deleting encrypted data
+//!
+//! Updates re-encrypt a row's value with `EqlOperation::Store`, and Postgres
+//! then has to add an entry for the new value to every index on the column.
+//! To see what each index costs, every operation is timed with each index
+//! alone, with all of them and with none. Indexes are left out by dropping
+//! them inside a transaction that is rolled back after the timing, which
+//! restores them without a rebuild. Each statement is rolled back to a
+//! savepoint after it's timed, so every one runs against the original rows.
+//! With no index on the column, updates can be HOT (heap-only), so the
+//! difference from the `none` timings is the index maintenance cost.
+//!
+//! Postgres doesn't touch indexes when it deletes a row (vacuum removes the
+//! dead entries later), so deletes are timed with every index in place, where
+//! their cost is finding the rows: by an encrypted equality or an ORE range.
+//!
+//! Encrypting the values and running the statement are timed separately.
+
+use crate::{Latencies, LatencySummary, MixedTable, MixedWorkload, Param, ParamOp, QueryParam};
+use anyhow::{bail, Context, Result};
+use fake::rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A statement that modifies an encrypted table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modification {
+    /// `UPDATE ... SET value = $1 WHERE id = $2`, with a new value for a random row.
+    Update,
+    /// `DELETE ... WHERE value = $1`, for a value sampled from the table.
+    DeleteEq,
+    /// `DELETE ... WHERE value >= $1 AND value < $2`, with ORE terms for a
+    /// range that holds about [`ModifyOptions::delete_range_rows`] rows.
+    /// Integer tables only.
+    DeleteRange,
+}
+
+impl Modification {
+    pub const ALL: [Self; 3] = [Self::Update, Self::DeleteEq, Self::DeleteRange];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Update => "update",
+            Self::DeleteEq => "delete_eq",
+            Self::DeleteRange => "delete_range",
+        }
+    }
+
+    /// Whether the operation maintains indexes, and so is timed with each set of them.
+    pub fn maintains_indexes(&self) -> bool {
+        matches!(self, Self::Update)
+    }
+
+    /// Whether the operation can run on `table`.
+    pub fn supports(&self, table: MixedTable) -> bool {
+        !matches!((self, table), (Self::DeleteRange, MixedTable::String))
+    }
+}
+
+impl FromStr for Modification {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "update" => Ok(Self::Update),
+            "delete_eq" => Ok(Self::DeleteEq),
+            "delete_range" => Ok(Self::DeleteRange),
+            other => {
+                bail!("OPERATIONS must be one of: update, delete_eq, delete_range (got {other:?})")
+            }
+        }
+    }
+}
+
+/// An index on the encrypted column.
+#[derive(Debug, Clone, Serialize)]
+pub struct TableIndex {
+    pub name: String,
+    /// `ore` (the EQL operator class), `hash`, `gin` or, for anything else, the index name.
+    pub kind: String,
+}
+
+impl TableIndex {
+    /// The indexes on `table` other than its primary key.
+    pub async fn list(db: &PgPool, table: &str) -> Result<Vec<Self>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT i.relname::text, pg_get_indexdef(x.indexrelid)
+             FROM pg_index x
+             JOIN pg_class i ON i.oid = x.indexrelid
+             WHERE x.indrelid = $1::regclass AND NOT x.indisprimary
+             ORDER BY i.relname",
+        )
+        .bind(table)
+        .fetch_all(db)
+        .await
+        .with_context(|| format!("failed to list the indexes on {table}"))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(name, definition)| {
+                let definition = definition.to_ascii_lowercase();
+                let kind = if definition.contains("encrypted_operator_class") {
+                    "ore".to_string()
+                } else if definition.contains("using hash") {
+                    "hash".to_string()
+                } else if definition.contains("using gin") {
+                    "gin".to_string()
+                } else {
+                    name.clone()
+                };
+                Self { name, kind }
+            })
+            .collect())
+    }
+}
+
+/// The indexes kept while an operation is timed; the others are dropped.
+#[derive(Debug, Clone)]
+pub struct IndexSet {
+    /// `none`, the kind of a single index or `all`.
+    pub name: String,
+    drop: Vec<String>,
+}
+
+impl IndexSet {
+    /// No indexes, each index on its own, then all of them. With a single
+    /// index, that index alone is the last set.
+    pub fn sets(indexes: &[TableIndex]) -> Vec<Self> {
+        let names: Vec<String> = indexes.iter().map(|i| i.name.clone()).collect();
+        let mut sets = vec![Self {
+            name: "none".to_string(),
+            drop: names.clone(),
+        }];
+        for index in indexes {
+            sets.push(Self {
+                name: index.kind.clone(),
+                drop: names
+                    .iter()
+                    .filter(|n| **n != index.name)
+                    .cloned()
+                    .collect(),
+            });
+        }
+        if indexes.len() > 1 {
+            sets.push(Self {
+                name: "all".to_string(),
+                drop: Vec::new(),
+            });
+        }
+        sets
+    }
+}
+
+/// How each operation is timed.
+#[derive(Debug, Clone)]
+pub struct ModifyOptions {
+    /// Statements run per operation and index set.
+    pub iterations: usize,
+    /// Rows an ORE range delete aims to match.
+    pub delete_range_rows: u32,
+    /// Seed for the rows and values; every index set gets the same ones.
+    pub seed: u64,
+}
+
+/// Time `options.iterations` of `op` on the workload's table with only the
+/// indexes in `set`, in a transaction that is then rolled back. Each statement
+/// is rolled back to a savepoint once it has been timed.
+pub async fn run_modification(
+    workload: &MixedWorkload,
+    db: &PgPool,
+    op: Modification,
+    set: &IndexSet,
+    options: &ModifyOptions,
+) -> Result<ModificationResult> {
+    if !op.supports(workload.table) {
+        bail!("{} isn't supported on {}", op.as_str(), workload.table_name);
+    }
+    let table = &workload.table_name;
+    // Roughly the width of a range holding `delete_range_rows` of the table's
+    // values, which are spread over every i32
+    let rows = (*workload.ids.end() - *workload.ids.start() + 1).max(1) as f64;
+    let range_width = (u32::MAX as f64 / rows * options.delete_range_rows as f64) as i64;
+
+    let mut tx = db.begin().await?;
+    for index in &set.drop {
+        sqlx::query(&format!("DROP INDEX {index}"))
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("failed to drop {index}"))?;
+    }
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut encrypt = Latencies::new();
+    let mut execute = Latencies::new();
+    let mut affected = 0;
+    for i in 0..options.iterations {
+        let key = workload.keys[i % workload.keys.len()].clone();
+        let builder = workload.builder().index_type(workload.table.lookup_index());
+        let builder = match op {
+            Modification::Update => {
+                let id = rng.random_range(workload.ids.clone());
+                builder
+                    .statement(format!("UPDATE {table} SET value = $1 WHERE id = $2"))
+                    .param(
+                        QueryParam::encrypted(workload.table.generate(&mut rng)).op(ParamOp::Store),
+                    )
+                    .param(QueryParam::plain(id))
+            }
+            Modification::DeleteEq => builder
+                .statement(format!("DELETE FROM {table} WHERE value = $1"))
+                .param(QueryParam::encrypted(key)),
+            Modification::DeleteRange => {
+                let low = key.as_i64().context("range deletes need integer values")?;
+                let high = (low + range_width).min(i32::MAX as i64) as i32;
+                builder
+                    .statement(format!(
+                        "DELETE FROM {table} WHERE value >= $1 AND value < $2"
+                    ))
+                    .param(QueryParam::encrypted(key))
+                    .param(QueryParam::encrypted(Param::Int(high)))
+            }
+        };
+
+        let started = Instant::now();
+        let query = builder.build(Arc::clone(&workload.cipher)).await?;
+        encrypt.record(started.elapsed());
+
+        // Each statement is undone before the next, so every one sees the
+        // original table rather than one earlier deletes have shrunk
+        sqlx::query("SAVEPOINT modify").execute(&mut *tx).await?;
+        let started = Instant::now();
+        affected += sqlx::query_with(&query.statement, query.arguments()?)
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("failed to run {}", query.statement))?
+            .rows_affected();
+        execute.record(started.elapsed());
+        sqlx::query("ROLLBACK TO SAVEPOINT modify")
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.rollback().await?;
+
+    Ok(ModificationResult {
+        operation: op.as_str().to_string(),
+        indexes: set.name.clone(),
+        iterations: options.iterations,
+        rows_per_op: affected as f64 / options.iterations.max(1) as f64,
+        encrypt: encrypt.summary(),
+        execute: execute.summary(),
+    })
+}
+
+/// Timings of one operation with one set of indexes.
+#[derive(Debug, Serialize)]
+pub struct ModificationResult {
+    pub operation: String,
+    /// The [`IndexSet`] the operation ran with.
+    pub indexes: String,
+    pub iterations: usize,
+    /// Mean rows updated or deleted per statement.
+    pub rows_per_op: f64,
+    /// Time to encrypt the statement's values.
+    pub encrypt: LatencySummary,
+    /// Time to run the statement.
+    pub execute: LatencySummary,
+}
+
+/// The update and delete timings of one table.
+#[derive(Debug, Serialize)]
+pub struct ModifyReport {
+    pub table: String,
+    pub target_rows: String,
+    pub indexes: Vec<TableIndex>,
+    pub results: Vec<ModificationResult>,
+}
+
+impl ModifyReport {
+    pub fn new(
+        workload: &MixedWorkload,
+        target_rows: impl Into<String>,
+        indexes: Vec<TableIndex>,
+        results: Vec<ModificationResult>,
+    ) -> Self {
+        Self {
+            table: workload.table.as_str().to_string(),
+            target_rows: target_rows.into(),
+            indexes,
+            results,
+        }
+    }
+
+    /// Print a summary and write the report to
+    /// `results/modify/{table}_rows_{n}.json`.
+    pub fn write(&self) -> Result<PathBuf> {
+        println!("{}_encrypted_{}:", self.table, self.target_rows);
+        for r in &self.results {
+            println!(
+                "  {} (indexes: {}): execute p50 {:.2}ms, mean {:.2}ms, p99 {:.2}ms; encrypt mean {:.2}ms; {:.1} rows/op",
+                r.operation,
+                r.indexes,
+                r.execute.p50_ms,
+                r.execute.mean_ms,
+                r.execute.p99_ms,
+                r.encrypt.mean_ms,
+                r.rows_per_op,
+            );
+        }
+
+        let path = PathBuf::from(format!(
+            "results/modify/{}_rows_{}.json",
+            self.table, self.target_rows
+        ));
+        fs::create_dir_all("results/modify")?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,409 @@
+//! Query parameters for [`crate::EncryptedQueryBuilder`]
+//!
+//! A query can take any number of parameters. Each is either a plaintext that
+//! is encrypted for an index type (a range bound, a value to match) or a plain
+//! value bound as is (a `LIMIT` or `OFFSET`). Parameters are numbered `$1`,
+//! `$2`, ... in the order they're added. A parameter can also be given a name
+//! and referred to as `:name` in the statement, which is rewritten to its
+//! position before the query runs.
+//!
+//! Encrypted parameters are encrypted as a query term for their index type by
+//! default. SteVec (JSON) queries also need selectors for a path, terms for
+//! the value at a path and full ciphertexts to test containment with, which
+//! are chosen with a [`ParamOp`].
+
+use crate::WrappedJson;
+use anyhow::{anyhow, bail, Context, Result};
+use cipherstash_client::{
+    encryption::{Plaintext, QueryOp},
+    eql::{EqlCiphertext, EqlOperation},
+    schema::{column::IndexType, ColumnType},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgArguments, types::Json, Arguments};
+
+/// A value bound to a query without encryption.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Param {
+    SmallInt(i16),
+    Int(i32),
+    BigInt(i64),
+    Boolean(bool),
+    Text(String),
+    Json(serde_json::Value),
+}
+
+impl Param {
+    /// Convert a JSON value to the type of a `cast` column.
+    pub fn new(cast: ColumnType, value: &serde_json::Value) -> Result<Self> {
+        let as_int = || {
+            value
+                .as_i64()
+                .with_context(|| format!("expected an integer parameter, got {value}"))
+        };
+
+        Ok(match cast {
+            ColumnType::SmallInt => Self::SmallInt(i16::try_from(as_int()?)?),
+            ColumnType::Int => Self::Int(i32::try_from(as_int()?)?),
+            ColumnType::BigInt => Self::BigInt(as_int()?),
+            ColumnType::Boolean => Self::Boolean(
+                value
+                    .as_bool()
+                    .with_context(|| format!("expected a boolean parameter, got {value}"))?,
+            ),
+            ColumnType::Utf8Str => Self::Text(
+                value
+                    .as_str()
+                    .with_context(|| format!("expected a string parameter, got {value}"))?
+                    .to_string(),
+            ),
+            ColumnType::JsonB => Self::Json(value.clone()),
+            other => bail!("{other} parameters aren't supported"),
+        })
+    }
+
+    /// Pick the type from the JSON value itself: integers are bound as `BIGINT`.
+    pub fn infer(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Bool(v) => Self::Boolean(*v),
+            serde_json::Value::String(v) => Self::Text(v.clone()),
+            v => match v.as_i64() {
+                Some(v) => Self::BigInt(v),
+                None => Self::Json(v.clone()),
+            },
+        }
+    }
+
+    /// The integer value, if this is an integer.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::SmallInt(v) => Some(*v as i64),
+            Self::Int(v) => Some(*v as i64),
+            Self::BigInt(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn add_to(&self, args: &mut PgArguments) -> Result<()> {
+        match self {
+            Self::SmallInt(v) => args.add(v),
+            Self::Int(v) => args.add(v),
+            Self::BigInt(v) => args.add(v),
+            Self::Boolean(v) => args.add(v),
+            Self::Text(v) => args.add(v),
+            Self::Json(v) => args.add(v),
+        }
+        .map_err(|err| anyhow!(err))
+    }
+}
+
+impl From<Param> for Plaintext {
+    fn from(param: Param) -> Self {
+        match param {
+            Param::SmallInt(v) => v.into(),
+            Param::Int(v) => v.into(),
+            Param::BigInt(v) => v.into(),
+            Param::Boolean(v) => v.into(),
+            Param::Text(v) => v.into(),
+            Param::Json(v) => WrappedJson(v).into(),
+        }
+    }
+}
+
+impl From<i16> for Param {
+    fn from(v: i16) -> Self {
+        Self::SmallInt(v)
+    }
+}
+
+impl From<i32> for Param {
+    fn from(v: i32) -> Self {
+        Self::Int(v)
+    }
+}
+
+impl From<i64> for Param {
+    fn from(v: i64) -> Self {
+        Self::BigInt(v)
+    }
+}
+
+impl From<bool> for Param {
+    fn from(v: bool) -> Self {
+        Self::Boolean(v)
+    }
+}
+
+impl From<String> for Param {
+    fn from(v: String) -> Self {
+        Self::Text(v)
+    }
+}
+
+impl From<&str> for Param {
+    fn from(v: &str) -> Self {
+        Self::Text(v.to_string())
+    }
+}
+
+impl From<serde_json::Value> for Param {
+    fn from(v: serde_json::Value) -> Self {
+        Self::Json(v)
+    }
+}
+
+/// What an encrypted parameter is encrypted as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ParamOp {
+    /// The query term for the index type, such as an ORE term or a bloom filter.
+    #[default]
+    Default,
+    /// A SteVec selector for a JSON path such as `$.user.age`, to select a
+    /// field or check that it exists.
+    SteVecSelector,
+    /// A SteVec term for a JSON string or number, to compare a selected field with.
+    SteVecTerm,
+    /// A full ciphertext, as stored. SteVec containment (`@>`) needs the
+    /// encrypted vector of every path in the document.
+    Store,
+}
+
+impl ParamOp {
+    /// The operation to pass to `encrypt_eql` for `index_type`.
+    pub fn eql_operation(self, index_type: &IndexType) -> EqlOperation<'_> {
+        match self {
+            Self::Default => EqlOperation::Query(index_type, QueryOp::Default),
+            Self::SteVecSelector => EqlOperation::Query(index_type, QueryOp::SteVecSelector),
+            Self::SteVecTerm => EqlOperation::Query(index_type, QueryOp::SteVecTerm),
+            Self::Store => EqlOperation::Store,
+        }
+    }
+
+    /// The plaintext to encrypt `param` as.
+    ///
+    /// Selectors are paths and terms are scalars, so JSON strings and numbers
+    /// are unwrapped for them. JSON numbers are indexed as floats, so terms
+    /// for numbers are floats too.
+    pub fn plaintext(self, param: Param) -> Plaintext {
+        match (self, param) {
+            (
+                Self::SteVecSelector | Self::SteVecTerm,
+                Param::Json(serde_json::Value::String(s)),
+            ) => s.into(),
+            (Self::SteVecTerm, Param::Json(serde_json::Value::Number(n))) => {
+                n.as_f64().unwrap_or_default().into()
+            }
+            (_, param) => param.into(),
+        }
+    }
+}
+
+/// A parameter to add to an [`crate::EncryptedQueryBuilder`].
+#[derive(Debug)]
+pub struct QueryParam {
+    pub(crate) name: Option<String>,
+    pub(crate) kind: QueryParamKind,
+}
+
+#[derive(Debug)]
+pub(crate) enum QueryParamKind {
+    Encrypted {
+        plaintext: Plaintext,
+        /// Falls back to the builder's index type when not set.
+        index_type: Option<IndexType>,
+        op: ParamOp,
+    },
+    Plain(Param),
+}
+
+impl QueryParam {
+    /// A plaintext to encrypt, for the builder's index type unless [`Self::index_type`] is set.
+    pub fn encrypted(plaintext: impl Into<Plaintext>) -> Self {
+        Self {
+            name: None,
+            kind: QueryParamKind::Encrypted {
+                plaintext: plaintext.into(),
+                index_type: None,
+                op: ParamOp::Default,
+            },
+        }
+    }
+
+    /// A value bound without encryption.
+    pub fn plain(value: impl Into<Param>) -> Self {
+        Self {
+            name: None,
+            kind: QueryParamKind::Plain(value.into()),
+        }
+    }
+
+    /// Refer to the parameter as `:name` in the statement.
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// The index type to encrypt the parameter for. Ignored for plain parameters.
+    pub fn index_type(mut self, index_type: IndexType) -> Self {
+        if let QueryParamKind::Encrypted {
+            index_type: ref mut slot,
+            ..
+        } = self.kind
+        {
+            *slot = Some(index_type);
+        }
+        self
+    }
+
+    /// What to encrypt the parameter as. Ignored for plain parameters.
+    pub fn op(mut self, op: ParamOp) -> Self {
+        if let QueryParamKind::Encrypted {
+            op: ref mut slot, ..
+        } = self.kind
+        {
+            *slot = op;
+        }
+        self
+    }
+}
+
+/// A parameter of a built query.
+#[derive(Debug, Clone)]
+pub enum BoundParam {
+    Encrypted(Box<EqlCiphertext>),
+    Plain(Param),
+}
+
+impl BoundParam {
+    pub(crate) fn add_to(&self, args: &mut PgArguments) -> Result<()> {
+        match self {
+            Self::Encrypted(eql) => args.add(Json(eql)).map_err(|err| anyhow!(err)),
+            Self::Plain(param) => param.add_to(args),
+        }
+    }
+}
+
+/// Rewrite `:name` placeholders in `statement` to `$n`, where `n` is the
+/// position of the parameter with that name.
+///
+/// `::` casts and quoted strings are left alone. Statements are returned
+/// unchanged when no parameter has a name.
+pub(crate) fn positional_statement(statement: &str, names: &[Option<&str>]) -> Result<String> {
+    if names.iter().all(Option::is_none) {
+        return Ok(statement.to_string());
+    }
+
+    let mut out = String::with_capacity(statement.len());
+    let mut chars = statement.chars().peekable();
+    let mut in_quotes = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_quotes = !in_quotes;
+                out.push(c);
+            }
+            ':' if !in_quotes && chars.peek() == Some(&':') => {
+                chars.next();
+                out.push_str("::");
+            }
+            ':' if !in_quotes
+                && chars
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || *c == '_') =>
+            {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                let position = names
+                    .iter()
+                    .position(|n| *n == Some(name.as_str()))
+                    .with_context(|| format!("statement refers to unknown parameter :{name}"))?;
+                out.push_str(&format!("${}", position + 1));
+            }
+            c => out.push(c),
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positional_statement_rewrites_names_in_order() {
+        let statement = positional_statement(
+            "SELECT id FROM t WHERE value > :low AND value < :high LIMIT :limit",
+            &[Some("low"), Some("high"), Some("limit")],
+        )
+        .unwrap();
+
+        assert_eq!(
+            statement,
+            "SELECT id FROM t WHERE value > $1 AND value < $2 LIMIT $3"
+        );
+    }
+
+    #[test]
+    fn positional_statement_reuses_the_position_of_repeated_names() {
+        let statement = positional_statement(
+            "SELECT id FROM t WHERE value >= :v OR value <= :v LIMIT :limit",
+            &[Some("v"), Some("limit")],
+        )
+        .unwrap();
+
+        assert_eq!(
+            statement,
+            "SELECT id FROM t WHERE value >= $1 OR value <= $1 LIMIT $2"
+        );
+    }
+
+    #[test]
+    fn positional_statement_leaves_casts_and_quoted_strings_alone() {
+        let statement = positional_statement(
+            "SELECT value::jsonb, ':low' FROM t WHERE hmac(value) = hmac(:low::jsonb)",
+            &[Some("low")],
+        )
+        .unwrap();
+
+        assert_eq!(
+            statement,
+            "SELECT value::jsonb, ':low' FROM t WHERE hmac(value) = hmac($1::jsonb)"
+        );
+    }
+
+    #[test]
+    fn positional_statement_counts_unnamed_parameters() {
+        let statement = positional_statement(
+            "SELECT id FROM t WHERE a = $1 AND b = :b",
+            &[None, Some("b")],
+        )
+        .unwrap();
+
+        assert_eq!(statement, "SELECT id FROM t WHERE a = $1 AND b = $2");
+    }
+
+    #[test]
+    fn positional_statement_is_unchanged_without_names() {
+        let statement = "SELECT id FROM t WHERE value = $1 AND x = :y";
+
+        assert_eq!(positional_statement(statement, &[None]).unwrap(), statement);
+    }
+
+    #[test]
+    fn positional_statement_rejects_unknown_names() {
+        let err =
+            positional_statement("SELECT id FROM t WHERE value = :nope", &[Some("v")]).unwrap_err();
+
+        assert!(err.to_string().contains(":nope"), "{err}");
+    }
+}
@@ -0,0 +1,303 @@
+//! Pools of query parameters drawn from a distribution
+//!
+//! Running the same query with the same encrypted parameter on every iteration
+//! lets the buffer cache and plan cache flatter the results. A scenario
+//! parameter with a `distribution` is instead drawn afresh for each query in a
+//! pool: the queries are encrypted up front and the benches rotate through
+//! them. Draws are seeded, so a pool is the same on every run with the same
+//! `POOL_SEED`.
+//!
+//! Because each parameter can match a different number of rows, every query in
+//! a pool is also timed on its own and the spread across parameters is written
+//! to `results/query/pool_{bench}_rows_{n}.json`.
+
+use crate::env_vars::{env_opt, env_or};
+use crate::{EncryptedQuery, Param, PlaintextQuery};
+use anyhow::{bail, Context, Result};
+use fake::rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::fs;
+use std::path::PathBuf;
+
+/// Queries in a pool when a scenario has a distribution but doesn't set `pool`.
+pub const DEFAULT_POOL_SIZE: usize = 32;
+const DEFAULT_PROFILE_RUNS: usize = 3;
+
+/// Where the values of a pooled parameter come from.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Distribution {
+    /// Integers between `min` and `max`, inclusive.
+    Uniform { min: i64, max: i64 },
+    /// Integers from a normal distribution, rounded.
+    Normal { mean: f64, std_dev: f64 },
+    /// One of `values`, with equal probability.
+    Choice { values: Vec<serde_json::Value> },
+    /// Values sampled from the plaintext table, optionally through an SQL
+    /// expression on `value` such as `split_part(value, ' ', 2)`.
+    Table {
+        #[serde(default)]
+        expr: Option<String>,
+    },
+}
+
+impl Distribution {
+    /// Draw `n` values. `key` makes draws for different parameters independent.
+    pub(crate) async fn draw(
+        &self,
+        n: usize,
+        seed: u64,
+        key: &str,
+        db: &PgPool,
+        plaintext_table: &str,
+    ) -> Result<Vec<serde_json::Value>> {
+        let mut rng = pool_rng(seed, key);
+
+        Ok(match self {
+            Self::Uniform { min, max } => {
+                if min > max {
+                    bail!("uniform distribution has min {min} > max {max}");
+                }
+                (0..n)
+                    .map(|_| rng.random_range(*min..=*max).into())
+                    .collect()
+            }
+            Self::Normal { mean, std_dev } => (0..n)
+                .map(|_| {
+                    // Box-Muller, with u1 in (0, 1] so the log is finite
+                    let u1 = 1.0 - rng.random::<f64>();
+                    let u2 = rng.random::<f64>();
+                    let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                    ((mean + z * std_dev).round() as i64).into()
+                })
+                .collect(),
+            Self::Choice { values } => {
+                if values.is_empty() {
+                    bail!("choice distribution has no values");
+                }
+                (0..n)
+                    .map(|_| values[rng.random_range(0..values.len())].clone())
+                    .collect()
+            }
+            Self::Table { expr } => {
+                let expr = expr.as_deref().unwrap_or("value");
+                let sampled: Vec<serde_json::Value> = sqlx::query_scalar(&format!(
+                    "SELECT to_jsonb({expr}) FROM {plaintext_table}
+                     ORDER BY md5(id::text || $1) LIMIT $2"
+                ))
+                .bind(format!("{seed}:{key}"))
+                .bind(n as i64)
+                .fetch_all(db)
+                .await
+                .with_context(|| format!("failed to sample parameters from {plaintext_table}"))?;
+
+                if sampled.is_empty() {
+                    bail!("can't sample parameters from {plaintext_table}: it's empty");
+                }
+                sampled.into_iter().cycle().take(n).collect()
+            }
+        })
+    }
+}
+
+fn pool_rng(seed: u64, key: &str) -> StdRng {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&seed.to_le_bytes());
+    hasher.update(key.as_bytes());
+    StdRng::from_seed(*hasher.finalize().as_bytes())
+}
+
+/// How parameter pools are drawn and profiled.
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    /// Overrides the pool size of every scenario with a distribution.
+    pub size: Option<usize>,
+    pub seed: u64,
+    /// Times each pooled query is run when measuring the spread.
+    pub profile_runs: usize,
+}
+
+impl PoolOptions {
+    /// Read `PARAM_POOL_SIZE`, `POOL_SEED` (default 0) and `POOL_PROFILE_RUNS`.
+    pub fn from_env() -> Result<Self> {
+        let size = env_opt("PARAM_POOL_SIZE")?;
+        let seed = env_or("POOL_SEED", 0)?;
+        let profile_runs = env_or("POOL_PROFILE_RUNS", DEFAULT_PROFILE_RUNS)?;
+
+        Ok(Self {
+            size,
+            seed,
+            profile_runs,
+        })
+    }
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            size: None,
+            seed: 0,
+            profile_runs: DEFAULT_PROFILE_RUNS,
+        }
+    }
+}
+
+/// The queries built for one scenario, one per set of drawn parameters.
+pub struct QueryPool {
+    pub queries: Vec<EncryptedQuery>,
+    /// The plaintext parameters of each query, in order.
+    pub params: Vec<Vec<Param>>,
+    /// The scenario's `baseline` with each query's parameters, if it has one.
+    pub baselines: Vec<PlaintextQuery>,
+}
+
+impl QueryPool {
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// The queries in order, repeating forever.
+    pub fn cycle(&self) -> impl Iterator<Item = &EncryptedQuery> {
+        self.queries.iter().cycle()
+    }
+
+    /// The plaintext baselines in order, repeating forever.
+    pub fn cycle_baselines(&self) -> impl Iterator<Item = &PlaintextQuery> {
+        self.baselines.iter().cycle()
+    }
+}
+
+/// Latency statistics across the parameters of a pool, in milliseconds.
+#[derive(Debug, Serialize)]
+pub struct Spread {
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    pub std_dev_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+impl Spread {
+    pub fn new(samples_ms: &[f64]) -> Self {
+        let mut sorted = samples_ms.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let n = sorted.len().max(1) as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let variance = sorted.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / n;
+        let percentile = |p: f64| {
+            let rank = ((p * sorted.len() as f64).ceil() as usize).saturating_sub(1);
+            sorted.get(rank).copied().unwrap_or(0.0)
+        };
+
+        Self {
+            min_ms: sorted.first().copied().unwrap_or(0.0),
+            max_ms: sorted.last().copied().unwrap_or(0.0),
+            mean_ms: mean,
+            std_dev_ms: variance.sqrt(),
+            p50_ms: percentile(0.5),
+            p95_ms: percentile(0.95),
+        }
+    }
+}
+
+/// Mean latency of one pooled query.
+#[derive(Debug, Serialize)]
+pub struct ParamTiming {
+    pub params: Vec<Param>,
+    pub rows: usize,
+    pub execute_ms: f64,
+    pub decrypt_ms: f64,
+}
+
+/// Per-parameter timings of one scenario's pool.
+#[derive(Debug, Serialize)]
+pub struct PoolProfile {
+    pub scenario: String,
+    pub pool_size: usize,
+    pub execute: Spread,
+    pub decrypt: Spread,
+    pub min_rows: usize,
+    pub max_rows: usize,
+    pub params: Vec<ParamTiming>,
+}
+
+impl PoolProfile {
+    pub fn new(scenario: impl Into<String>, params: Vec<ParamTiming>) -> Self {
+        let spread =
+            |f: fn(&ParamTiming) -> f64| Spread::new(&params.iter().map(f).collect::<Vec<_>>());
+
+        Self {
+            scenario: scenario.into(),
+            pool_size: params.len(),
+            execute: spread(|p| p.execute_ms),
+            decrypt: spread(|p| p.decrypt_ms),
+            min_rows: params.iter().map(|p| p.rows).min().unwrap_or(0),
+            max_rows: params.iter().map(|p| p.rows).max().unwrap_or(0),
+            params,
+        }
+    }
+}
+
+/// Pool profiles for every pooled scenario in a query bench.
+#[derive(Debug, Serialize)]
+pub struct PoolReport {
+    pub bench: String,
+    pub target_rows: String,
+    pub seed: u64,
+    pub scenarios: Vec<PoolProfile>,
+}
+
+impl PoolReport {
+    pub fn new(bench: impl Into<String>, target_rows: impl Into<String>, seed: u64) -> Self {
+        Self {
+            bench: bench.into(),
+            target_rows: target_rows.into(),
+            seed,
+            scenarios: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, profile: PoolProfile) {
+        self.scenarios.push(profile);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenarios.is_empty()
+    }
+
+    /// Print a summary and write the report to `results/query/pool_{bench}_rows_{n}.json`.
+    pub fn write(&self) -> Result<PathBuf> {
+        for p in &self.scenarios {
+            println!(
+                "{}/{}: {} params, execute mean {:.2}ms (min {:.2}ms, p95 {:.2}ms, max {:.2}ms, stddev {:.2}ms), {}-{} rows",
+                self.bench,
+                p.scenario,
+                p.pool_size,
+                p.execute.mean_ms,
+                p.execute.min_ms,
+                p.execute.p95_ms,
+                p.execute.max_ms,
+                p.execute.std_dev_ms,
+                p.min_rows,
+                p.max_rows,
+            );
+        }
+
+        let path = PathBuf::from(format!(
+            "results/query/pool_{}_rows_{}.json",
+            self.bench, self.target_rows
+        ));
+        fs::create_dir_all("results/query")?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+}
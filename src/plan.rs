@@ -0,0 +1,283 @@
+//! Query plans captured with `EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON)`
+//!
+//! Timings alone don't show whether a query used `integer_encrypted_eql_index`
+//! or fell back to a sequential scan. Each query is run once under `EXPLAIN`
+//! with its real encrypted parameters, and the plan is written to
+//! `results/query/plan_{bench}_rows_{n}.json` along with a summary of the scans
+//! it used.
+//!
+//! A scenario can also name the index it expects its plan to use. Every query
+//! in a scenario's parameter pool is explained, since the planner can pick a
+//! different plan for a parameter that matches more rows. When any of them
+//! doesn't use the index, the scenario is flagged, or the bench fails before
+//! timing anything with `INDEX_CHECK=fail`.
+
+use crate::EncryptedQuery;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// What to do when a plan doesn't use the index its scenario expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexCheck {
+    /// Don't check.
+    Off,
+    /// Flag the scenario in the plan report and carry on timing it.
+    #[default]
+    Warn,
+    /// Fail the bench before any scenario is timed.
+    Fail,
+}
+
+impl IndexCheck {
+    /// Read `INDEX_CHECK` (`off`, `warn` or `fail`), defaulting to `warn`.
+    pub fn from_env() -> Result<Self> {
+        env::var("INDEX_CHECK")
+            .map(|s| s.parse())
+            .unwrap_or(Ok(Self::default()))
+    }
+}
+
+impl FromStr for IndexCheck {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "fail" => Ok(Self::Fail),
+            other => bail!("index check must be one of: off, warn, fail (got {other:?})"),
+        }
+    }
+}
+
+impl EncryptedQuery {
+    /// Run the query under `EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON)` with its bound parameters.
+    pub async fn explain(&self, pool: &PgPool) -> Result<Value> {
+        let statement = format!("EXPLAIN (ANALYZE, BUFFERS, FORMAT JSON) {}", self.statement);
+        let plan: Value = sqlx::query_scalar_with(&statement, self.arguments()?)
+            .fetch_one(pool)
+            .await
+            .with_context(|| format!("failed to explain {}", self.statement))?;
+
+        Ok(plan)
+    }
+}
+
+/// A scan of a table in a plan.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PlanScan {
+    /// `Seq Scan`, `Index Scan`, `Bitmap Index Scan`, ...
+    pub node_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+}
+
+impl PlanScan {
+    pub fn is_seq_scan(&self) -> bool {
+        self.node_type == "Seq Scan"
+    }
+}
+
+impl std::fmt::Display for PlanScan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.index, &self.relation) {
+            (Some(index), _) => write!(f, "{} using {index}", self.node_type),
+            (None, Some(relation)) => write!(f, "{} on {relation}", self.node_type),
+            (None, None) => f.write_str(&self.node_type),
+        }
+    }
+}
+
+/// The plan of one scenario's query and the numbers worth comparing across runs.
+#[derive(Debug, Serialize)]
+pub struct QueryPlan {
+    pub scenario: String,
+    pub statement: String,
+    pub scans: Vec<PlanScan>,
+    pub planning_ms: f64,
+    pub execution_ms: f64,
+    pub shared_hit_blocks: u64,
+    pub shared_read_blocks: u64,
+    /// The index the scenario expects the plan to use, if it names one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_index: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_index_used: Option<bool>,
+    /// Queries in the scenario's pool that were explained.
+    pub queries_explained: usize,
+    /// How many of them don't use the expected index.
+    pub queries_missing_index: usize,
+    /// The full `EXPLAIN` output.
+    pub plan: Value,
+}
+
+impl QueryPlan {
+    pub fn new(scenario: impl Into<String>, query: &EncryptedQuery, plan: Value) -> Self {
+        // FORMAT JSON returns a one-element array
+        let root = plan.get(0).unwrap_or(&plan);
+        let node = &root["Plan"];
+        let mut scans = Vec::new();
+        collect_scans(node, &mut scans);
+
+        Self {
+            scenario: scenario.into(),
+            statement: query.statement.clone(),
+            scans,
+            planning_ms: root["Planning Time"].as_f64().unwrap_or_default(),
+            execution_ms: root["Execution Time"].as_f64().unwrap_or_default(),
+            // Buffer counts on a node include its children
+            shared_hit_blocks: node["Shared Hit Blocks"].as_u64().unwrap_or_default(),
+            shared_read_blocks: node["Shared Read Blocks"].as_u64().unwrap_or_default(),
+            expected_index: None,
+            expected_index_used: None,
+            queries_explained: 1,
+            queries_missing_index: 0,
+            plan,
+        }
+    }
+
+    /// Explain every query in a scenario's pool and check each one uses
+    /// `index`. The plan kept is the first that misses the index, or the
+    /// first query's if they all use it.
+    pub async fn explain_pool(
+        scenario: &str,
+        queries: &[EncryptedQuery],
+        pool: &PgPool,
+        index: Option<String>,
+    ) -> Result<Self> {
+        let mut kept: Option<Self> = None;
+        let mut missing = 0;
+        for query in queries {
+            let plan =
+                Self::new(scenario, query, query.explain(pool).await?).expect_index(index.clone());
+            if plan.misses_expected_index() {
+                missing += 1;
+            }
+            let keep = match &kept {
+                None => true,
+                Some(kept) => !kept.misses_expected_index() && plan.misses_expected_index(),
+            };
+            if keep {
+                kept = Some(plan);
+            }
+        }
+
+        let mut plan = kept.with_context(|| format!("{scenario} has no queries to explain"))?;
+        plan.queries_explained = queries.len();
+        plan.queries_missing_index = missing;
+        Ok(plan)
+    }
+
+    /// Check whether the plan uses `index`.
+    pub fn expect_index(mut self, index: Option<String>) -> Self {
+        self.expected_index_used = index
+            .as_deref()
+            .map(|index| self.indexes().any(|used| used == index));
+        self.expected_index = index;
+        self
+    }
+
+    /// Whether the scenario expects an index the plan doesn't use.
+    pub fn misses_expected_index(&self) -> bool {
+        self.expected_index_used == Some(false)
+    }
+
+    /// Names of the indexes the plan scans.
+    pub fn indexes(&self) -> impl Iterator<Item = &str> {
+        self.scans.iter().filter_map(|s| s.index.as_deref())
+    }
+
+    pub fn has_seq_scan(&self) -> bool {
+        self.scans.iter().any(PlanScan::is_seq_scan)
+    }
+}
+
+fn collect_scans(node: &Value, scans: &mut Vec<PlanScan>) {
+    let node_type = node["Node Type"].as_str().unwrap_or_default();
+    if node_type.ends_with("Scan") {
+        scans.push(PlanScan {
+            node_type: node_type.to_string(),
+            relation: node["Relation Name"].as_str().map(String::from),
+            index: node["Index Name"].as_str().map(String::from),
+        });
+    }
+    if let Some(children) = node["Plans"].as_array() {
+        for child in children {
+            collect_scans(child, scans);
+        }
+    }
+}
+
+/// Plans for every scenario in a query bench.
+#[derive(Debug, Serialize)]
+pub struct PlanReport {
+    pub bench: String,
+    pub target_rows: String,
+    pub scenarios: Vec<QueryPlan>,
+}
+
+impl PlanReport {
+    pub fn new(bench: impl Into<String>, target_rows: impl Into<String>) -> Self {
+        Self {
+            bench: bench.into(),
+            target_rows: target_rows.into(),
+            scenarios: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, plan: QueryPlan) {
+        self.scenarios.push(plan);
+    }
+
+    /// Plans that don't use the index their scenario expects.
+    pub fn missed_indexes(&self) -> impl Iterator<Item = &QueryPlan> {
+        self.scenarios.iter().filter(|p| p.misses_expected_index())
+    }
+
+    /// Print a summary and write the report to `results/query/plan_{bench}_rows_{n}.json`.
+    pub fn write(&self) -> Result<PathBuf> {
+        for p in &self.scenarios {
+            let scans: Vec<String> = p.scans.iter().map(ToString::to_string).collect();
+            println!(
+                "{:<5} {}/{}: {}, {:.2}ms, {} shared hits, {} reads",
+                if p.misses_expected_index() {
+                    "MISS"
+                } else if p.has_seq_scan() {
+                    "SEQ"
+                } else {
+                    "PLAN"
+                },
+                self.bench,
+                p.scenario,
+                scans.join(", "),
+                p.execution_ms,
+                p.shared_hit_blocks,
+                p.shared_read_blocks,
+            );
+            if let (true, Some(index)) = (p.misses_expected_index(), &p.expected_index) {
+                println!(
+                    "        expected {index}, missed by {} of {} queries",
+                    p.queries_missing_index, p.queries_explained
+                );
+            }
+        }
+
+        let path = PathBuf::from(format!(
+            "results/query/plan_{}_rows_{}.json",
+            self.bench, self.target_rows
+        ));
+        fs::create_dir_all("results/query")?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,875 @@
+//! Declarative query scenarios
+//!
+//! A scenario file describes a set of queries against one encrypted table, so
+//! new queries can be benchmarked without writing a new bench. Files are TOML:
+//!
+//! ```toml
+//! name = "ore"                  # prefix for benchmark IDs and reports
+//! group = "ORE"                 # criterion benchmark group
+//! table = "integer_encrypted"   # suffixed with `_{TARGET_ROWS}` for the standard sizes
+//! decrypt = "int"               # type the results are decrypted to
+//!
+//! [column]
+//! cast = "int"                  # cipherstash column type (int, big-int, utf8-str, jsonb, ...)
+//! indexes = ["ore"]             # ore, match, unique or ste-vec
+//!
+//! [[scenarios]]
+//! name = "range_gt_10"
+//! sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1 LIMIT 10"
+//! param = 5000
+//! index = "ore"                 # optional, defaults to the column's first index
+//! predicate = "value > $1"      # optional, the plaintext equivalent used by VERIFY
+//! expect_index = "{TABLE}_eql_index"  # optional, checked against the query plan
+//! ```
+//!
+//! Each scenario's `param` is encrypted for its index type and bound as `$1`.
+//! Scenarios with several parameters list them in `params` instead, each bound
+//! in order (`$1`, `$2`, ...) or referred to by name:
+//!
+//! ```toml
+//! [[scenarios]]
+//! name = "range_between_10"
+//! sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value > :low AND value < :high LIMIT :limit"
+//! params = [
+//!   { name = "low", value = 1000 },
+//!   { name = "high", value = 5000, index = "ore" },
+//!   { name = "limit", value = 10, plain = true },  # bound without encryption
+//! ]
+//! predicate = "value > :low AND value < :high"
+//! ```
+//!
+//! A parameter can be drawn from a `distribution` instead of having a fixed
+//! `value`. The scenario then builds a pool of `pool` queries (32 by default),
+//! each with freshly drawn parameters, and the benches rotate through them:
+//!
+//! ```toml
+//! [[scenarios]]
+//! name = "exact_random"
+//! sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value = $1 LIMIT 1"
+//! distribution = { kind = "uniform", min = 1, max = 10000 }
+//! pool = 64
+//! ```
+//!
+//! Distributions are `uniform` (`min`, `max`), `normal` (`mean`, `std_dev`),
+//! `choice` (`values`) and `table`, which samples the plaintext table through
+//! an optional SQL `expr` on `value`. Parameters in `params` take a
+//! `distribution` the same way.
+//!
+//! Encrypted parameters are query terms for their index by default. SteVec
+//! (JSON) queries pick what to encrypt with `op`: `ste-vec-selector` for a
+//! path, `ste-vec-term` for a value to compare a field with, or `store` for a
+//! full ciphertext to test containment with:
+//!
+//! ```toml
+//! [[scenarios]]
+//! name = "age_gt_65"
+//! sql = "SELECT id,value::jsonb FROM {TABLE} WHERE (value -> $1::jsonb::eql_v2_encrypted) > $2::jsonb LIMIT 10"
+//! params = [
+//!   { value = "$.age", op = "ste-vec-selector" },
+//!   { value = 65, op = "ste-vec-term" },
+//! ]
+//! predicate = "jsonb_path_query_first(value, ($1 #>> '{}')::jsonpath) > $2"
+//! ```
+//!
+//! Scenarios without a `predicate` are skipped when verifying.
+//!
+//! A scenario's `baseline` is the same query written for the plaintext table,
+//! with the same parameters bound unencrypted. The query bench times it as
+//! `{name}_plaintext` so the report can show the cost of encryption:
+//!
+//! ```toml
+//! [[scenarios]]
+//! name = "range_gt_10"
+//! sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1 LIMIT 10"
+//! param = 5000
+//! baseline = "SELECT id,value FROM {TABLE} WHERE value > $1 LIMIT 10"
+//! ```
+
+use crate::{
+    param::{positional_statement, ParamOp},
+    param_pool::{
+        Distribution, ParamTiming, PoolOptions, PoolProfile, QueryPool, DEFAULT_POOL_SIZE,
+    },
+    verify_query, Cipher, EncryptedQuery, EncryptedQueryBuilder, Param, PlaintextQuery, QueryParam,
+    StreamTiming, Verification,
+};
+use anyhow::{bail, Context, Result};
+use cipherstash_client::{
+    eql::Identifier,
+    schema::{
+        column::{Index, IndexType},
+        ColumnConfig, ColumnType,
+    },
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Row counts that have their own `{table}_{rows}` tables.
+const TABLE_SIZES: &[&str] = &["10000", "100000", "1000000", "10000000"];
+
+/// Directory searched for scenario files when none is given.
+pub const DEFAULT_SCENARIO_DIR: &str = "scenarios";
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioFile {
+    pub name: String,
+    pub group: String,
+    pub table: String,
+    /// Defaults to `table` with `_encrypted` replaced by `_plaintext`.
+    #[serde(default)]
+    pub plaintext_table: Option<String>,
+    pub decrypt: DecryptType,
+    pub column: ColumnSpec,
+    pub scenarios: Vec<Scenario>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColumnSpec {
+    pub cast: ColumnType,
+    pub indexes: Vec<IndexKind>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    pub sql: String,
+    /// Shorthand for a single encrypted parameter.
+    #[serde(default)]
+    pub param: Option<serde_json::Value>,
+    #[serde(default)]
+    pub params: Vec<ScenarioParam>,
+    /// Draw the `param` shorthand from a distribution instead.
+    #[serde(default)]
+    pub distribution: Option<Distribution>,
+    /// Number of queries to draw when a parameter has a distribution.
+    #[serde(default)]
+    pub pool: Option<usize>,
+    /// Index type for encrypted parameters that don't set their own.
+    #[serde(default)]
+    pub index: Option<IndexKind>,
+    /// What to encrypt the `param` shorthand as.
+    #[serde(default)]
+    pub op: ParamOp,
+    #[serde(default)]
+    pub predicate: Option<String>,
+    /// The same query on the plaintext table, with `{TABLE}` replaced by its name.
+    #[serde(default)]
+    pub baseline: Option<String>,
+    /// Index the query plan should use, with `{TABLE}` replaced by the table name.
+    #[serde(default)]
+    pub expect_index: Option<String>,
+    /// The index can return rows that don't match, such as bloom filter false
+    /// positives, so VERIFY reports them without failing.
+    #[serde(default)]
+    pub approximate: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioParam {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub value: Option<serde_json::Value>,
+    /// Draw the value from a distribution instead, see [`crate::param_pool`].
+    #[serde(default)]
+    pub distribution: Option<Distribution>,
+    #[serde(default)]
+    pub index: Option<IndexKind>,
+    /// What to encrypt the value as.
+    #[serde(default)]
+    pub op: ParamOp,
+    /// Bind the value as is rather than encrypting it.
+    #[serde(default)]
+    pub plain: bool,
+}
+
+impl Scenario {
+    /// Every parameter in order, including the `param` shorthand.
+    pub fn all_params(&self) -> Vec<ScenarioParam> {
+        let shorthand =
+            (self.param.is_some() || self.distribution.is_some()).then(|| ScenarioParam {
+                name: None,
+                value: self.param.clone(),
+                distribution: self.distribution.clone(),
+                index: None,
+                op: self.op,
+                plain: false,
+            });
+        shorthand.into_iter().chain(self.params.clone()).collect()
+    }
+
+    /// The number of queries to build: one unless a parameter has a distribution.
+    pub fn pool_size(&self, options: &PoolOptions) -> usize {
+        if self.all_params().iter().any(|p| p.distribution.is_some()) {
+            options
+                .size
+                .or(self.pool)
+                .unwrap_or(DEFAULT_POOL_SIZE)
+                .max(1)
+        } else {
+            1
+        }
+    }
+
+    /// The name of the index the plan should use on `table_name`, if any.
+    pub fn expected_index(&self, table_name: &str) -> Option<String> {
+        self.expect_index
+            .as_ref()
+            .map(|index| index.replace("{TABLE}", table_name))
+    }
+
+    fn names(&self) -> Vec<Option<String>> {
+        self.all_params().into_iter().map(|p| p.name).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IndexKind {
+    Ore,
+    Match,
+    Unique,
+    SteVec,
+}
+
+impl IndexKind {
+    pub fn index(&self) -> Index {
+        match self {
+            Self::Ore => Index::new_ore(),
+            Self::Match => Index::new_match(),
+            Self::Unique => Index::new_unique(),
+            // FIXME: There is no convenience method for SteVec yet on Index
+            Self::SteVec => Index::new(IndexType::SteVec {
+                prefix: "value".to_string(),
+                term_filters: Default::default(),
+            }),
+        }
+    }
+}
+
+/// The Rust type query results are decrypted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DecryptType {
+    SmallInt,
+    Int,
+    BigInt,
+    Boolean,
+    String,
+    Json,
+}
+
+impl DecryptType {
+    /// Run `query` and decrypt every row, returning the number of rows.
+    pub async fn execute_and_decrypt(
+        &self,
+        query: &EncryptedQuery,
+        pool: &PgPool,
+    ) -> Result<usize> {
+        Ok(match self {
+            Self::SmallInt => query.execute_and_decrypt::<i16>(pool).await?.len(),
+            Self::Int => query.execute_and_decrypt::<i32>(pool).await?.len(),
+            Self::BigInt => query.execute_and_decrypt::<i64>(pool).await?.len(),
+            Self::Boolean => query.execute_and_decrypt::<bool>(pool).await?.len(),
+            Self::String => query.execute_and_decrypt::<String>(pool).await?.len(),
+            Self::Json => query
+                .execute_and_decrypt::<serde_json::Value>(pool)
+                .await?
+                .len(),
+        })
+    }
+
+    /// Stream `query`, decrypting rows as they arrive, and time the first row and the total.
+    pub async fn time_fetch_and_decrypt(
+        &self,
+        query: &EncryptedQuery,
+        pool: &PgPool,
+        chunk_size: usize,
+    ) -> Result<StreamTiming> {
+        match self {
+            Self::SmallInt => query.time_fetch_and_decrypt::<i16>(pool, chunk_size).await,
+            Self::Int => query.time_fetch_and_decrypt::<i32>(pool, chunk_size).await,
+            Self::BigInt => query.time_fetch_and_decrypt::<i64>(pool, chunk_size).await,
+            Self::Boolean => query.time_fetch_and_decrypt::<bool>(pool, chunk_size).await,
+            Self::String => {
+                query
+                    .time_fetch_and_decrypt::<String>(pool, chunk_size)
+                    .await
+            }
+            Self::Json => {
+                query
+                    .time_fetch_and_decrypt::<serde_json::Value>(pool, chunk_size)
+                    .await
+            }
+        }
+    }
+}
+
+impl ScenarioFile {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read scenario file {}", path.display()))?;
+        Self::parse(&source)
+            .with_context(|| format!("failed to parse scenario file {}", path.display()))
+    }
+
+    /// Parse the contents of a scenario file and check each scenario is complete.
+    fn parse(source: &str) -> Result<Self> {
+        let file: Self = toml::from_str(source)?;
+
+        if file.column.indexes.is_empty() {
+            bail!("column.indexes must list at least one index");
+        }
+        for scenario in &file.scenarios {
+            if (scenario.param.is_some() || scenario.distribution.is_some())
+                && !scenario.params.is_empty()
+            {
+                bail!("scenario {} sets both param and params", scenario.name);
+            }
+            if let Some(p) = scenario
+                .params
+                .iter()
+                .find(|p| p.value.is_none() && p.distribution.is_none())
+            {
+                bail!(
+                    "a parameter of scenario {} needs a value or a distribution: {p:?}",
+                    scenario.name
+                );
+            }
+            let indexes = scenario
+                .params
+                .iter()
+                .filter_map(|p| p.index)
+                .chain(scenario.index);
+            for index in indexes {
+                if !file.column.indexes.contains(&index) {
+                    bail!(
+                        "scenario {} uses a {index:?} index, which the column doesn't have",
+                        scenario.name
+                    );
+                }
+            }
+
+            let names = scenario.names();
+            let names: Vec<Option<&str>> = names.iter().map(Option::as_deref).collect();
+            let statements = [
+                ("sql", Some(&scenario.sql)),
+                ("baseline", scenario.baseline.as_ref()),
+                ("predicate", scenario.predicate.as_ref()),
+            ];
+            for (key, statement) in statements {
+                let Some(statement) = statement else {
+                    continue;
+                };
+                if key != "predicate" && !statement.contains("{TABLE}") {
+                    bail!(
+                        "the {key} of scenario {} must query {{TABLE}}",
+                        scenario.name
+                    );
+                }
+                positional_statement(statement, &names)
+                    .with_context(|| format!("invalid {key} in scenario {}", scenario.name))?;
+            }
+        }
+
+        Ok(file)
+    }
+
+    /// Every `*.toml` file in `dir`, sorted by path.
+    pub fn paths_in(dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let dir = dir.as_ref();
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)
+            .with_context(|| format!("failed to read scenario directory {}", dir.display()))?
+        {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "toml") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// The encrypted table for `target_rows`, or the base table for other row counts.
+    pub fn table_name(&self, target_rows: &str) -> String {
+        sized_table(&self.table, target_rows)
+    }
+
+    pub fn plaintext_table_name(&self, target_rows: &str) -> String {
+        let base = self
+            .plaintext_table
+            .clone()
+            .unwrap_or_else(|| self.table.replace("_encrypted", "_plaintext"));
+        sized_table(&base, target_rows)
+    }
+
+    pub fn column_config(&self) -> ColumnConfig {
+        self.column.indexes.iter().fold(
+            ColumnConfig::build("value").casts_as(self.column.cast),
+            |config, index| config.add_index(index.index()),
+        )
+    }
+
+    /// Draw the parameters of every scenario, encrypt them and build the queries
+    /// against the table for `target_rows`.
+    pub async fn build_queries(
+        &self,
+        db: &PgPool,
+        target_rows: &str,
+        cipher: Arc<Cipher>,
+        options: &PoolOptions,
+    ) -> Result<Vec<QueryPool>> {
+        let table_name = self.table_name(target_rows);
+        let plaintext_table = self.plaintext_table_name(target_rows);
+
+        let mut pools = Vec::with_capacity(self.scenarios.len());
+        for scenario in &self.scenarios {
+            let context = || format!("failed to build queries for scenario {}", scenario.name);
+            let params = self
+                .draw_params(scenario, db, &plaintext_table, options)
+                .await
+                .with_context(context)?;
+            let index = scenario.index.unwrap_or(self.column.indexes[0]);
+
+            let mut queries = Vec::with_capacity(params.len());
+            for values in &params {
+                let mut builder = EncryptedQueryBuilder::new(
+                    self.column_config(),
+                    Identifier::new(&table_name, "value"),
+                )
+                .index_type(index.index().index_type)
+                .statement(scenario.sql.replace("{TABLE}", &table_name));
+
+                for (p, value) in scenario.all_params().into_iter().zip(values) {
+                    let mut param = if p.plain {
+                        QueryParam::plain(value.clone())
+                    } else {
+                        QueryParam::encrypted(p.op.plaintext(value.clone()))
+                            .index_type(p.index.unwrap_or(index).index().index_type)
+                            .op(p.op)
+                    };
+                    if let Some(name) = p.name {
+                        param = param.named(name);
+                    }
+                    builder = builder.param(param);
+                }
+
+                queries.push(
+                    builder
+                        .build(Arc::clone(&cipher))
+                        .await
+                        .with_context(context)?,
+                );
+            }
+
+            let baselines = match &scenario.baseline {
+                Some(baseline) => {
+                    let names = scenario.names();
+                    let names: Vec<Option<&str>> = names.iter().map(Option::as_deref).collect();
+                    let statement = baseline.replace("{TABLE}", &plaintext_table);
+                    params
+                        .iter()
+                        .map(|values| PlaintextQuery::new(&statement, &names, values.clone()))
+                        .collect::<Result<_>>()
+                        .with_context(context)?
+                }
+                None => Vec::new(),
+            };
+
+            pools.push(QueryPool {
+                queries,
+                params,
+                baselines,
+            });
+        }
+        Ok(pools)
+    }
+
+    /// The plaintext parameters of each query in the scenario's pool.
+    async fn draw_params(
+        &self,
+        scenario: &Scenario,
+        db: &PgPool,
+        plaintext_table: &str,
+        options: &PoolOptions,
+    ) -> Result<Vec<Vec<Param>>> {
+        let size = scenario.pool_size(options);
+        let mut drawn = vec![Vec::new(); size];
+
+        for (i, p) in scenario.all_params().iter().enumerate() {
+            let values = match (&p.distribution, &p.value) {
+                (Some(distribution), _) => {
+                    let key = format!("{}/{}/{i}", self.name, scenario.name);
+                    distribution
+                        .draw(size, options.seed, &key, db, plaintext_table)
+                        .await?
+                }
+                (None, Some(value)) => vec![value.clone(); size],
+                (None, None) => bail!("parameter {} has no value", i + 1),
+            };
+
+            for (query_params, value) in drawn.iter_mut().zip(&values) {
+                let param = if p.plain {
+                    Param::infer(value)
+                } else {
+                    Param::new(self.column.cast, value)
+                        .with_context(|| format!("invalid value for parameter {}", i + 1))?
+                };
+                query_params.push(param);
+            }
+        }
+
+        Ok(drawn)
+    }
+
+    /// Check each query in `queries` against the plaintext table, if the scenario has a predicate.
+    ///
+    /// The predicate can refer to the scenario's parameters by position or by name.
+    /// Queries in a pool of more than one are reported as `{scenario}#{i}`.
+    pub async fn verify(
+        &self,
+        scenario: &Scenario,
+        queries: &QueryPool,
+        pool: &PgPool,
+        plaintext_table: &str,
+    ) -> Result<Vec<Verification>> {
+        let Some(predicate) = &scenario.predicate else {
+            return Ok(Vec::new());
+        };
+        let names = scenario.names();
+        let names: Vec<Option<&str>> = names.iter().map(Option::as_deref).collect();
+        let predicate = positional_statement(predicate, &names)?;
+
+        let mut verifications = Vec::with_capacity(queries.len());
+        for i in 0..queries.len() {
+            verifications.push(
+                self.verify_one(scenario, queries, i, pool, plaintext_table, &predicate)
+                    .await?,
+            );
+        }
+        Ok(verifications)
+    }
+
+    /// Check query `i` of a scenario's pool.
+    async fn verify_one(
+        &self,
+        scenario: &Scenario,
+        queries: &QueryPool,
+        i: usize,
+        pool: &PgPool,
+        plaintext_table: &str,
+        predicate: &str,
+    ) -> Result<Verification> {
+        let name = if queries.len() > 1 {
+            format!("{}#{i}", scenario.name)
+        } else {
+            scenario.name.clone()
+        };
+        let (name, query, params) = (&name, &queries.queries[i], &queries.params[i]);
+        let approximate = scenario.approximate;
+
+        Ok(match self.decrypt {
+            DecryptType::SmallInt => {
+                verify_query::<i16>(
+                    query,
+                    pool,
+                    name,
+                    plaintext_table,
+                    predicate,
+                    params,
+                    approximate,
+                )
+                .await?
+            }
+            DecryptType::Int => {
+                verify_query::<i32>(
+                    query,
+                    pool,
+                    name,
+                    plaintext_table,
+                    predicate,
+                    params,
+                    approximate,
+                )
+                .await?
+            }
+            DecryptType::BigInt => {
+                verify_query::<i64>(
+                    query,
+                    pool,
+                    name,
+                    plaintext_table,
+                    predicate,
+                    params,
+                    approximate,
+                )
+                .await?
+            }
+            DecryptType::Boolean => {
+                verify_query::<bool>(
+                    query,
+                    pool,
+                    name,
+                    plaintext_table,
+                    predicate,
+                    params,
+                    approximate,
+                )
+                .await?
+            }
+            DecryptType::String => {
+                verify_query::<String>(
+                    query,
+                    pool,
+                    name,
+                    plaintext_table,
+                    predicate,
+                    params,
+                    approximate,
+                )
+                .await?
+            }
+            DecryptType::Json => {
+                verify_query::<serde_json::Value>(
+                    query,
+                    pool,
+                    name,
+                    plaintext_table,
+                    predicate,
+                    params,
+                    approximate,
+                )
+                .await?
+            }
+        })
+    }
+
+    /// Time each query in a pool on its own, to show how much the parameters matter.
+    pub async fn profile_pool(
+        &self,
+        scenario: &Scenario,
+        queries: &QueryPool,
+        pool: &PgPool,
+        runs: usize,
+    ) -> Result<PoolProfile> {
+        let runs = runs.max(1);
+        let mut timings = Vec::with_capacity(queries.len());
+
+        for (query, params) in queries.queries.iter().zip(&queries.params) {
+            let mut rows = 0;
+            let mut execute = Duration::ZERO;
+            let mut decrypt = Duration::ZERO;
+            for _ in 0..runs {
+                let started = Instant::now();
+                rows = query.execute(pool).await?.len();
+                execute += started.elapsed();
+
+                let started = Instant::now();
+                self.decrypt.execute_and_decrypt(query, pool).await?;
+                decrypt += started.elapsed();
+            }
+
+            timings.push(ParamTiming {
+                params: params.clone(),
+                rows,
+                execute_ms: execute.as_secs_f64() * 1000.0 / runs as f64,
+                decrypt_ms: decrypt.as_secs_f64() * 1000.0 / runs as f64,
+            });
+        }
+
+        Ok(PoolProfile::new(&scenario.name, timings))
+    }
+}
+
+fn sized_table(base: &str, target_rows: &str) -> String {
+    if TABLE_SIZES.contains(&target_rows) {
+        format!("{base}_{target_rows}")
+    } else {
+        base.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = r#"
+name = "test"
+group = "TEST"
+table = "integer_encrypted"
+decrypt = "int"
+
+[column]
+cast = "int"
+indexes = ["ore"]
+"#;
+
+    fn parse(scenarios: &str) -> Result<ScenarioFile> {
+        ScenarioFile::parse(&format!("{HEADER}{scenarios}"))
+    }
+
+    fn parse_err(scenarios: &str) -> String {
+        format!("{:#}", parse(scenarios).unwrap_err())
+    }
+
+    #[test]
+    fn scenario_files_in_the_repo_parse() {
+        for path in ScenarioFile::paths_in(DEFAULT_SCENARIO_DIR).unwrap() {
+            ScenarioFile::load(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn parse_accepts_a_scenario_with_a_param() {
+        let file = parse(
+            r#"
+[[scenarios]]
+name = "range_gt_10"
+sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1 LIMIT 10"
+param = 5000
+predicate = "value > $1"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(file.scenarios.len(), 1);
+        assert_eq!(file.scenarios[0].all_params().len(), 1);
+    }
+
+    #[test]
+    fn parse_rejects_an_unknown_op() {
+        let err = parse_err(
+            r#"
+[[scenarios]]
+name = "bad_op"
+sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1"
+param = 5000
+op = "ste-vec-everything"
+"#,
+        );
+
+        assert!(
+            err.contains("unknown variant `ste-vec-everything`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parse_rejects_param_and_params_together() {
+        let err = parse_err(
+            r#"
+[[scenarios]]
+name = "both"
+sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1 AND value < $2"
+param = 1000
+params = [{ value = 5000 }]
+"#,
+        );
+
+        assert!(
+            err.contains("scenario both sets both param and params"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parse_accepts_named_params_mixed_with_positional_ones() {
+        let file = parse(
+            r#"
+[[scenarios]]
+name = "mixed"
+sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1 AND value < :high LIMIT :limit"
+params = [
+  { value = 1000 },
+  { name = "high", value = 5000 },
+  { name = "limit", value = 10, plain = true },
+]
+predicate = "value > $1 AND value < :high"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            file.scenarios[0].names(),
+            [None, Some("high".to_string()), Some("limit".to_string())]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_a_name_no_param_has() {
+        let err = parse_err(
+            r#"
+[[scenarios]]
+name = "unknown_name"
+sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1 AND value < :high"
+params = [{ value = 1000 }, { name = "top", value = 5000 }]
+"#,
+        );
+
+        assert!(
+            err.contains("invalid sql in scenario unknown_name"),
+            "{err}"
+        );
+        assert!(err.contains(":high"), "{err}");
+    }
+
+    #[test]
+    fn parse_rejects_sql_without_the_table_placeholder() {
+        let err = parse_err(
+            r#"
+[[scenarios]]
+name = "no_table"
+sql = "SELECT id,value::jsonb FROM integer_encrypted WHERE value > $1"
+param = 5000
+"#,
+        );
+
+        assert!(
+            err.contains("sql of scenario no_table must query {TABLE}"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parse_rejects_a_baseline_without_the_table_placeholder() {
+        let err = parse_err(
+            r#"
+[[scenarios]]
+name = "no_table"
+sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value > $1"
+param = 5000
+baseline = "SELECT id,value FROM integer_plaintext WHERE value > $1"
+"#,
+        );
+
+        assert!(
+            err.contains("baseline of scenario no_table must query {TABLE}"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parse_rejects_an_index_the_column_lacks() {
+        let err = parse_err(
+            r#"
+[[scenarios]]
+name = "wrong_index"
+sql = "SELECT id,value::jsonb FROM {TABLE} WHERE value = $1"
+param = 5000
+index = "unique"
+"#,
+        );
+
+        assert!(err.contains("uses a Unique index"), "{err}");
+    }
+}
@@ -0,0 +1,143 @@
+//! Server-side execution times from `pg_stat_statements`
+//!
+//! Criterion times each query from the client, so network transfer and sqlx
+//! decoding are counted along with the time Postgres spends executing it. With
+//! `PG_STAT_STATEMENTS=true` the query bench resets `pg_stat_statements` before
+//! each scenario and reads it afterwards, so the two can be compared. This
+//! needs `shared_preload_libraries = 'pg_stat_statements'` on the server.
+//!
+//! Constants in statements (such as a literal `LIMIT`) are normalized by
+//! `pg_stat_statements`, so statements aren't matched by text: everything run
+//! between the reset and the read belongs to the scenario, and the statement
+//! with the most calls is reported.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Whether the query benches should record `pg_stat_statements` (`PG_STAT_STATEMENTS=true`).
+pub fn stat_statements_enabled() -> bool {
+    env::var("PG_STAT_STATEMENTS").is_ok_and(|v| v == "true" || v == "1")
+}
+
+/// Create the extension if needed and check the server has loaded it.
+pub async fn init_stat_statements(pool: &PgPool) -> Result<()> {
+    sqlx::query("CREATE EXTENSION IF NOT EXISTS pg_stat_statements")
+        .execute(pool)
+        .await?;
+    reset_stat_statements(pool)
+        .await
+        .context("pg_stat_statements must be in shared_preload_libraries")
+}
+
+/// Clear the statistics of every statement.
+pub async fn reset_stat_statements(pool: &PgPool) -> Result<()> {
+    sqlx::query("SELECT pg_stat_statements_reset()")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Statistics of one statement since the last reset.
+#[derive(Debug, Serialize, FromRow)]
+pub struct ServerStats {
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub scenario: String,
+    pub query: String,
+    pub calls: i64,
+    pub mean_ms: f64,
+    pub stddev_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    /// Total rows returned across all calls.
+    pub rows: i64,
+    pub shared_blks_hit: i64,
+    pub shared_blks_read: i64,
+}
+
+impl ServerStats {
+    /// The most called statement in the current database since the last reset,
+    /// leaving out the queries on `pg_stat_statements` itself.
+    pub async fn read(pool: &PgPool, scenario: impl Into<String>) -> Result<Option<Self>> {
+        let stats: Option<Self> = sqlx::query_as(
+            "SELECT query, calls, mean_exec_time AS mean_ms, stddev_exec_time AS stddev_ms,
+                    min_exec_time AS min_ms, max_exec_time AS max_ms, rows,
+                    shared_blks_hit, shared_blks_read
+             FROM pg_stat_statements
+             WHERE dbid = (SELECT oid FROM pg_database WHERE datname = current_database())
+               AND query NOT LIKE '%pg_stat_statements%'
+             ORDER BY calls DESC
+             LIMIT 1",
+        )
+        .fetch_optional(pool)
+        .await
+        .context("failed to read pg_stat_statements")?;
+
+        Ok(stats.map(|stats| Self {
+            scenario: scenario.into(),
+            ..stats
+        }))
+    }
+
+    pub fn rows_per_call(&self) -> f64 {
+        self.rows as f64 / self.calls.max(1) as f64
+    }
+}
+
+/// Server-side statistics for every scenario in a query bench.
+#[derive(Debug, Serialize)]
+pub struct ServerStatsReport {
+    pub bench: String,
+    pub target_rows: String,
+    pub scenarios: Vec<ServerStats>,
+}
+
+impl ServerStatsReport {
+    pub fn new(bench: impl Into<String>, target_rows: impl Into<String>) -> Self {
+        Self {
+            bench: bench.into(),
+            target_rows: target_rows.into(),
+            scenarios: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, stats: ServerStats) {
+        self.scenarios.push(stats);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenarios.is_empty()
+    }
+
+    /// Print a summary and write the report to `results/query/server_{bench}_rows_{n}.json`.
+    pub fn write(&self) -> Result<PathBuf> {
+        for s in &self.scenarios {
+            println!(
+                "{}/{}: {} calls, server mean {:.3}ms (stddev {:.3}ms, max {:.3}ms), {:.1} rows/call, {} shared hits, {} reads",
+                self.bench,
+                s.scenario,
+                s.calls,
+                s.mean_ms,
+                s.stddev_ms,
+                s.max_ms,
+                s.rows_per_call(),
+                s.shared_blks_hit,
+                s.shared_blks_read,
+            );
+        }
+
+        let path = PathBuf::from(format!(
+            "results/query/server_{}_rows_{}.json",
+            self.bench, self.target_rows
+        ));
+        fs::create_dir_all("results/query")?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,53 @@
+//! Per-batch timing of the ingest phases
+//!
+//! Each batch records how long it spent in each phase: generating plaintexts,
+//! encrypting them with `encrypt_eql`, serializing the ciphertexts to JSON and
+//! the database round trip that writes them. In pipelined mode batches overlap,
+//! so phase totals can add up to more than the wall time of the run.
+
+use crate::checkpoint::Batch;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub(crate) struct BatchTimings {
+    pub(crate) batch_index: usize,
+    pub(crate) rows: usize,
+    pub(crate) generate_seconds: f64,
+    pub(crate) encrypt_seconds: f64,
+    pub(crate) serialize_seconds: f64,
+    pub(crate) insert_seconds: f64,
+}
+
+impl BatchTimings {
+    pub(crate) fn new(batch: &Batch) -> Self {
+        Self {
+            batch_index: batch.index,
+            rows: batch.len,
+            generate_seconds: 0.0,
+            encrypt_seconds: 0.0,
+            serialize_seconds: 0.0,
+            insert_seconds: 0.0,
+        }
+    }
+}
+
+/// Time spent in each phase, summed over every batch of a run.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct PhaseTotals {
+    pub(crate) generate_seconds: f64,
+    pub(crate) encrypt_seconds: f64,
+    pub(crate) serialize_seconds: f64,
+    pub(crate) insert_seconds: f64,
+}
+
+impl PhaseTotals {
+    pub(crate) fn sum(timings: &[BatchTimings]) -> Self {
+        timings.iter().fold(Self::default(), |mut totals, t| {
+            totals.generate_seconds += t.generate_seconds;
+            totals.encrypt_seconds += t.encrypt_seconds;
+            totals.serialize_seconds += t.serialize_seconds;
+            totals.insert_seconds += t.insert_seconds;
+            totals
+        })
+    }
+}
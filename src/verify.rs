@@ -0,0 +1,291 @@
+//! Correctness checks for encrypted queries against their plaintext tables
+//!
+//! Plaintext tables written alongside the encrypted ones share their `id`s, so
+//! every decrypted row can be checked against the plaintext row with the same
+//! id. For each scenario the encrypted query is run and decrypted, then
+//! compared with the same predicate evaluated on the plaintext table:
+//! - extra rows: returned rows whose plaintext doesn't satisfy the predicate
+//! - false positives: the same, for approximate scenarios such as bloom filter
+//!   matches, where they're expected and don't count as a failure
+//! - missing rows: fewer matching rows than the plaintext table has (up to the
+//!   query's `LIMIT`)
+//! - order violations: for `ORDER BY value` queries, positions where the
+//!   decrypted value differs from the plaintext ordering
+//! - value mismatches: decrypted values that differ from the plaintext row
+//!
+//! Queries with a `LIMIT` but no `ORDER BY` may return any matching rows, so
+//! only membership and the row count are checked for them.
+
+use crate::{BoundParam, EncryptedQuery, Param};
+use anyhow::{anyhow, Context, Result};
+use cipherstash_client::encryption::Plaintext;
+use serde::Serialize;
+use sqlx::{postgres::PgArguments, Arguments, Decode, Encode, PgPool, Postgres, Type};
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Debug;
+use std::fs;
+use std::path::PathBuf;
+
+/// Problems recorded per scenario, beyond the counts.
+const MAX_SAMPLES: usize = 5;
+
+/// Whether the query benches should verify results instead of timing them (`VERIFY=true`).
+pub fn verify_enabled() -> bool {
+    env::var("VERIFY").is_ok_and(|v| v == "true" || v == "1")
+}
+
+/// The result of checking one scenario against its plaintext table.
+#[derive(Debug, Serialize)]
+pub struct Verification {
+    pub scenario: String,
+    pub returned: usize,
+    pub expected: usize,
+    pub missing: usize,
+    pub extra: usize,
+    pub false_positives: usize,
+    pub order_violations: usize,
+    pub value_mismatches: usize,
+    pub samples: Vec<String>,
+}
+
+impl Verification {
+    pub fn is_correct(&self) -> bool {
+        self.missing == 0
+            && self.extra == 0
+            && self.order_violations == 0
+            && self.value_mismatches == 0
+    }
+
+    fn sample(&mut self, problem: String) {
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push(problem);
+        }
+    }
+}
+
+/// Run `query` and check its decrypted results against `plaintext_table`.
+///
+/// `predicate` is the plaintext equivalent of the query's `WHERE` clause on
+/// the `value` column, with `params` (the plaintexts of the query's
+/// parameters, in order) bound as `$1`, `$2`, ... The `LIMIT` and
+/// `ORDER BY value` (ascending or descending) of the encrypted statement are
+/// applied to the plaintext side as well.
+///
+/// With `approximate`, returned rows that don't satisfy the predicate are
+/// counted as false positives instead of extra rows, and the rows they take up
+/// under the `LIMIT` aren't counted as missing.
+pub async fn verify_query<T>(
+    query: &EncryptedQuery,
+    pool: &PgPool,
+    scenario: &str,
+    plaintext_table: &str,
+    predicate: &str,
+    params: &[Param],
+    approximate: bool,
+) -> Result<Verification>
+where
+    T: TryFrom<Plaintext> + PartialEq + Debug + Send + Unpin,
+    T: for<'r> Decode<'r, Postgres> + Type<Postgres>,
+    <T as TryFrom<Plaintext>>::Error: Debug,
+{
+    let limit = statement_limit(query);
+    let order = statement_order(query);
+    // The ids or the limit follow the predicate's parameters
+    let next = params.len() + 1;
+
+    let rows: Vec<(i32, T)> = query.execute_and_decrypt_with_ids(pool).await?;
+    let ids: Vec<i32> = rows.iter().map(|(id, _)| *id).collect();
+
+    let plaintext: HashMap<i32, (T, bool)> = sqlx::query_as_with::<_, (i32, T, bool), _>(
+        &format!("SELECT id, value, ({predicate}) FROM {plaintext_table} WHERE id = ANY(${next})"),
+        arguments(params, &ids)?,
+    )
+    .fetch_all(pool)
+    .await
+    .with_context(|| format!("failed to read {plaintext_table}"))?
+    .into_iter()
+    .map(|(id, value, matches)| (id, (value, matches)))
+    .collect();
+
+    let expected: i64 = sqlx::query_scalar_with(
+        &format!(
+            "SELECT count(*) FROM (
+                SELECT 1 FROM {plaintext_table} WHERE {predicate} LIMIT ${next}
+            ) expected"
+        ),
+        arguments(params, limit)?,
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let mut verification = Verification {
+        scenario: scenario.to_string(),
+        returned: rows.len(),
+        expected: expected as usize,
+        missing: 0,
+        extra: 0,
+        false_positives: 0,
+        order_violations: 0,
+        value_mismatches: 0,
+        samples: Vec::new(),
+    };
+
+    for (id, value) in &rows {
+        match plaintext.get(id) {
+            None => {
+                verification.extra += 1;
+                verification.sample(format!("id {id} is not in {plaintext_table}"));
+            }
+            Some((plaintext_value, matches)) => {
+                if plaintext_value != value {
+                    verification.value_mismatches += 1;
+                    verification.sample(format!(
+                        "id {id} decrypted to {value:?} but the plaintext is {plaintext_value:?}"
+                    ));
+                }
+                if !matches && approximate {
+                    verification.false_positives += 1;
+                } else if !matches {
+                    verification.extra += 1;
+                    verification.sample(format!("id {id} ({plaintext_value:?}) doesn't match"));
+                }
+            }
+        }
+    }
+
+    let matched = verification.returned - verification.extra - verification.false_positives;
+    let wanted = match limit {
+        Some(limit) => verification
+            .expected
+            .min((limit as usize).saturating_sub(verification.false_positives)),
+        None => verification.expected,
+    };
+    verification.missing = wanted.saturating_sub(matched);
+    if verification.missing > 0 {
+        verification.sample(format!(
+            "{matched} matching rows returned, expected {wanted}"
+        ));
+    }
+
+    if let Some(order) = order {
+        let expected_order: Vec<T> = sqlx::query_scalar_with(
+            &format!(
+                "SELECT value FROM {plaintext_table} WHERE {predicate} ORDER BY value {order} LIMIT ${next}"
+            ),
+            arguments(params, limit)?,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        for (i, ((_, value), expected)) in rows.iter().zip(&expected_order).enumerate() {
+            if value != expected {
+                verification.order_violations += 1;
+                verification.sample(format!(
+                    "position {i} is {value:?} but should be {expected:?}"
+                ));
+            }
+        }
+    }
+
+    Ok(verification)
+}
+
+/// The direction of a query's `ORDER BY value`, if it has one.
+fn statement_order(query: &EncryptedQuery) -> Option<&'static str> {
+    let (_, order) = query.statement.split_once(" ORDER BY value")?;
+    let descending = order
+        .split_whitespace()
+        .next()
+        .is_some_and(|direction| direction.eq_ignore_ascii_case("DESC"));
+
+    Some(if descending { "DESC" } else { "ASC" })
+}
+
+/// The `LIMIT` of a query's statement, if it ends with one, either a literal or a plain parameter.
+fn statement_limit(query: &EncryptedQuery) -> Option<i64> {
+    let (_, limit) = query.statement.rsplit_once(" LIMIT ")?;
+    let limit = limit.trim();
+
+    match limit.strip_prefix('$') {
+        Some(position) => match query
+            .params
+            .get(position.parse::<usize>().ok()?.checked_sub(1)?)?
+        {
+            BoundParam::Plain(param) => param.as_i64(),
+            BoundParam::Encrypted(_) => None,
+        },
+        None => limit.parse().ok(),
+    }
+}
+
+/// `params` followed by `extra`.
+fn arguments<'q, E>(params: &[Param], extra: E) -> Result<PgArguments>
+where
+    E: 'q + Encode<'q, Postgres> + Type<Postgres>,
+{
+    let mut args = PgArguments::default();
+    for param in params {
+        param.add_to(&mut args)?;
+    }
+    args.add(extra).map_err(|err| anyhow!(err))?;
+    Ok(args)
+}
+
+/// Verification results for every scenario in a query bench.
+#[derive(Debug, Serialize)]
+pub struct VerificationReport {
+    pub bench: String,
+    pub target_rows: String,
+    pub scenarios: Vec<Verification>,
+}
+
+impl VerificationReport {
+    pub fn new(bench: impl Into<String>, target_rows: impl Into<String>) -> Self {
+        Self {
+            bench: bench.into(),
+            target_rows: target_rows.into(),
+            scenarios: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, verification: Verification) {
+        self.scenarios.push(verification);
+    }
+
+    pub fn is_correct(&self) -> bool {
+        self.scenarios.iter().all(Verification::is_correct)
+    }
+
+    /// Print a summary and write the report to `results/query/verify_{bench}_rows_{n}.json`.
+    pub fn write(&self) -> Result<PathBuf> {
+        for v in &self.scenarios {
+            println!(
+                "{:<5} {}/{}: returned {}, expected {}, missing {}, extra {}, false positives {}, order violations {}, value mismatches {}",
+                if v.is_correct() { "OK" } else { "FAIL" },
+                self.bench,
+                v.scenario,
+                v.returned,
+                v.expected,
+                v.missing,
+                v.extra,
+                v.false_positives,
+                v.order_violations,
+                v.value_mismatches,
+            );
+            for sample in &v.samples {
+                println!("        {sample}");
+            }
+        }
+
+        let path = PathBuf::from(format!(
+            "results/query/verify_{}_rows_{}.json",
+            self.bench, self.target_rows
+        ));
+        fs::create_dir_all("results/query")?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,718 @@
+//! Concurrent query workloads
+//!
+//! The query bench runs one query at a time, which gives the latency of each
+//! query on an idle server but says nothing about how it behaves under load. A
+//! workload is a weighted mix of scenarios from the scenario files, run by many
+//! clients at once for a fixed time. Each client picks a scenario by weight,
+//! runs its next pooled query and records the latency in an HDR histogram for
+//! that scenario, so tail percentiles stay exact over long runs.
+//!
+//! Weights are given as `name=weight` pairs, where the name is a scenario file
+//! (`ore=2`) or one scenario in it (`ore/range_gt_10=5`). Scenarios without a
+//! weight get 1, and a weight of 0 leaves a scenario out.
+//!
+//! Mixes run either closed-loop ([`run_load_test`]), where each client waits
+//! for its query before starting the next, or open-loop ([`run_open_loop`]),
+//! where queries start on a fixed schedule whether or not earlier ones have
+//! finished. A closed loop slows down along with the database, so queueing
+//! delay never shows up in its latencies (coordinated omission). The open loop
+//! measures each query from when it was scheduled to start, so time spent
+//! waiting behind slow queries is counted.
+
+use crate::{
+    Cipher, DecryptType, EncryptedQuery, Latencies, LatencySummary, PoolOptions, QueryPool,
+    ScenarioFile, DEFAULT_SCENARIO_DIR,
+};
+use anyhow::{bail, Context, Result};
+use fake::rand::{rngs::StdRng, Rng, SeedableRng};
+use hdrhistogram::serialization::{
+    interval_log::{IntervalLogWriterBuilder, Tag},
+    V2DeflateSerializer,
+};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Scenario files in the default mix.
+pub const DEFAULT_WORKLOAD_SCENARIOS: &[&str] = &["exact", "match", "ore"];
+
+/// One scenario in a mix with its queries.
+pub struct MixEntry {
+    /// `{file}/{scenario}`
+    pub name: String,
+    pub weight: u32,
+    pub decrypt: DecryptType,
+    pub pool: QueryPool,
+}
+
+impl MixEntry {
+    /// Run the `i`th query in the pool (wrapping), decrypting the results if
+    /// `decrypt` is set. Returns the number of rows.
+    pub async fn run(&self, i: usize, db: &PgPool, decrypt: bool) -> Result<usize> {
+        let query: &EncryptedQuery = &self.pool.queries[i % self.pool.len()];
+        if decrypt {
+            self.decrypt.execute_and_decrypt(query, db).await
+        } else {
+            Ok(query.execute(db).await?.len())
+        }
+    }
+}
+
+/// A weighted mix of scenarios to run concurrently.
+pub struct QueryMix {
+    pub entries: Vec<MixEntry>,
+    /// Running total of the weights, for picking an entry.
+    cumulative: Vec<u32>,
+}
+
+impl QueryMix {
+    /// Build the queries of every scenario in the `scenarios` files (names in
+    /// `scenarios/` or paths) with a non-zero weight.
+    pub async fn build(
+        scenarios: &[String],
+        weights: &str,
+        db: &PgPool,
+        target_rows: &str,
+        cipher: Arc<Cipher>,
+        options: &PoolOptions,
+    ) -> Result<Self> {
+        let weights = parse_weights(weights)?;
+
+        let mut entries = Vec::new();
+        for scenario in scenarios {
+            let path = if scenario.ends_with(".toml") {
+                PathBuf::from(scenario)
+            } else {
+                PathBuf::from(format!("{DEFAULT_SCENARIO_DIR}/{scenario}.toml"))
+            };
+            let file = ScenarioFile::load(&path)?;
+            let pools = file
+                .build_queries(db, target_rows, Arc::clone(&cipher), options)
+                .await?;
+
+            for (pool, s) in pools.into_iter().zip(&file.scenarios) {
+                let name = format!("{}/{}", file.name, s.name);
+                let weight = weights
+                    .get(&name)
+                    .or_else(|| weights.get(&file.name))
+                    .copied()
+                    .unwrap_or(1);
+                if weight > 0 {
+                    entries.push(MixEntry {
+                        name,
+                        weight,
+                        decrypt: file.decrypt,
+                        pool,
+                    });
+                }
+            }
+        }
+
+        Self::new(entries)
+    }
+
+    pub fn new(entries: Vec<MixEntry>) -> Result<Self> {
+        if entries.is_empty() {
+            bail!("the workload has no scenarios with a weight above 0");
+        }
+        let cumulative = entries
+            .iter()
+            .scan(0, |total, entry| {
+                *total += entry.weight;
+                Some(*total)
+            })
+            .collect();
+        Ok(Self {
+            entries,
+            cumulative,
+        })
+    }
+
+    /// The index of a random entry, chosen by weight.
+    pub fn pick(&self, rng: &mut impl Rng) -> usize {
+        let total = *self.cumulative.last().unwrap();
+        let n = rng.random_range(0..total);
+        self.cumulative.partition_point(|&c| c <= n)
+    }
+}
+
+/// Parse `name=weight` pairs separated by commas.
+pub(crate) fn parse_weights(weights: &str) -> Result<HashMap<String, u32>> {
+    weights
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|pair| {
+            let (name, weight) = pair
+                .split_once('=')
+                .with_context(|| format!("invalid weight {pair}, expected name=weight"))?;
+            let weight = weight
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid weight {pair}, expected an integer"))?;
+            Ok((name.trim().to_string(), weight))
+        })
+        .collect()
+}
+
+/// How a closed-loop load test is run.
+#[derive(Debug, Clone)]
+pub struct LoadTestOptions {
+    pub clients: usize,
+    /// Time spent running queries before any are recorded.
+    pub warmup: Duration,
+    pub duration: Duration,
+    pub decrypt: bool,
+    pub seed: u64,
+}
+
+/// Queries completed by one client, per mix entry.
+struct ClientResult {
+    latencies: Vec<Latencies>,
+    /// Queries that finished during the measured time.
+    completed: Vec<u64>,
+    errors: Vec<u64>,
+}
+
+/// Run `options.clients` clients against `db`, each running one query after
+/// another until the time is up.
+///
+/// Queries that start during the warmup aren't recorded, so the percentiles
+/// cover `options.duration` alone, and the QPS counts the queries that finished
+/// within it. Failed queries are counted but don't stop the run.
+pub async fn run_load_test(
+    mix: Arc<QueryMix>,
+    db: &PgPool,
+    options: &LoadTestOptions,
+) -> Result<Vec<ScenarioLoad>> {
+    let measure_from = Instant::now() + options.warmup;
+    let end = measure_from + options.duration;
+
+    let mut clients = JoinSet::new();
+    for client in 0..options.clients {
+        let mix = Arc::clone(&mix);
+        let db = db.clone();
+        let decrypt = options.decrypt;
+        let mut rng = StdRng::seed_from_u64(options.seed.wrapping_add(client as u64));
+
+        clients.spawn(async move {
+            let n = mix.entries.len();
+            let mut result = ClientResult {
+                latencies: vec![Latencies::new(); n],
+                completed: vec![0; n],
+                errors: vec![0; n],
+            };
+            // Start each client at a different query in the pools
+            let mut next = vec![client; n];
+
+            loop {
+                let started = Instant::now();
+                if started >= end {
+                    break;
+                }
+                let i = mix.pick(&mut rng);
+                let outcome = mix.entries[i].run(next[i], &db, decrypt).await;
+                next[i] += 1;
+
+                let finished = Instant::now();
+                if outcome.is_ok() && finished >= measure_from && finished < end {
+                    result.completed[i] += 1;
+                }
+                if started < measure_from {
+                    continue;
+                }
+                match outcome {
+                    Ok(_) => result.latencies[i].record(finished - started),
+                    Err(_) => result.errors[i] += 1,
+                }
+            }
+            result
+        });
+    }
+
+    let mut latencies = vec![Latencies::new(); mix.entries.len()];
+    let mut completed = vec![0; mix.entries.len()];
+    let mut errors = vec![0; mix.entries.len()];
+    while let Some(result) = clients.join_next().await {
+        let result = result.context("load test client panicked")?;
+        for (total, l) in latencies.iter_mut().zip(&result.latencies) {
+            total.add(l);
+        }
+        for (total, c) in completed.iter_mut().zip(&result.completed) {
+            *total += c;
+        }
+        for (total, e) in errors.iter_mut().zip(&result.errors) {
+            *total += e;
+        }
+    }
+
+    let seconds = options.duration.as_secs_f64();
+    Ok(mix
+        .entries
+        .iter()
+        .zip(latencies.iter().zip(completed.into_iter().zip(errors)))
+        .map(|(entry, (latencies, (completed, errors)))| ScenarioLoad {
+            scenario: entry.name.clone(),
+            weight: entry.weight,
+            qps: completed as f64 / seconds,
+            errors,
+            latency: latencies.summary(),
+        })
+        .collect())
+}
+
+/// Throughput and latency of one scenario in a load test.
+#[derive(Debug, Serialize)]
+pub struct ScenarioLoad {
+    pub scenario: String,
+    pub weight: u32,
+    pub qps: f64,
+    pub errors: u64,
+    pub latency: LatencySummary,
+}
+
+/// The results of a load test at one level of concurrency.
+#[derive(Debug, Serialize)]
+pub struct LoadTestReport {
+    pub target_rows: String,
+    pub clients: usize,
+    pub duration_seconds: f64,
+    pub decrypt: bool,
+    pub qps: f64,
+    pub errors: u64,
+    pub scenarios: Vec<ScenarioLoad>,
+}
+
+impl LoadTestReport {
+    pub fn new(
+        target_rows: impl Into<String>,
+        options: &LoadTestOptions,
+        scenarios: Vec<ScenarioLoad>,
+    ) -> Self {
+        Self {
+            target_rows: target_rows.into(),
+            clients: options.clients,
+            duration_seconds: options.duration.as_secs_f64(),
+            decrypt: options.decrypt,
+            qps: scenarios.iter().map(|s| s.qps).sum(),
+            errors: scenarios.iter().map(|s| s.errors).sum(),
+            scenarios,
+        }
+    }
+
+    /// Print a summary and write the report to
+    /// `results/load/load_test{_decrypt}_clients_{c}_rows_{n}.json`.
+    pub fn write(&self) -> Result<PathBuf> {
+        println!(
+            "{} clients: {:.1} queries/sec, {} errors",
+            self.clients, self.qps, self.errors
+        );
+        for s in &self.scenarios {
+            println!(
+                "  {}: {:.1} queries/sec, p50 {:.2}ms, p95 {:.2}ms, p99 {:.2}ms, p99.9 {:.2}ms, max {:.2}ms, {} errors",
+                s.scenario,
+                s.qps,
+                s.latency.p50_ms,
+                s.latency.p95_ms,
+                s.latency.p99_ms,
+                s.latency.p999_ms,
+                s.latency.max_ms,
+                s.errors,
+            );
+        }
+
+        let decrypt = if self.decrypt { "_decrypt" } else { "" };
+        let path = PathBuf::from(format!(
+            "results/load/load_test{decrypt}_clients_{}_rows_{}.json",
+            self.clients, self.target_rows
+        ));
+        fs::create_dir_all("results/load")?;
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        Ok(path)
+    }
+}
+
+/// How the start times of open-loop queries are spaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Arrivals {
+    /// Exactly `1 / rate` apart.
+    #[default]
+    Constant,
+    /// Exponentially distributed gaps with a mean of `1 / rate`, as from many
+    /// independent users.
+    Poisson,
+}
+
+impl Arrivals {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Constant => "constant",
+            Self::Poisson => "poisson",
+        }
+    }
+
+    /// The time from one scheduled query to the next.
+    fn gap(&self, rate: f64, rng: &mut impl Rng) -> Duration {
+        match self {
+            Self::Constant => Duration::from_secs_f64(1.0 / rate),
+            // Inverse transform sampling, with 1 - u in (0, 1] so ln is finite
+            Self::Poisson => Duration::from_secs_f64(-(1.0 - rng.random::<f64>()).ln() / rate),
+        }
+    }
+}
+
+impl FromStr for Arrivals {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "constant" => Ok(Self::Constant),
+            "poisson" => Ok(Self::Poisson),
+            other => bail!("arrivals must be one of: constant, poisson (got {other:?})"),
+        }
+    }
+}
+
+impl fmt::Display for Arrivals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How an open-loop run is scheduled.
+#[derive(Debug, Clone)]
+pub struct OpenLoopOptions {
+    /// Queries started per second.
+    pub rate: f64,
+    pub arrivals: Arrivals,
+    /// Time spent running queries before any are recorded.
+    pub warmup: Duration,
+    pub duration: Duration,
+    pub decrypt: bool,
+    pub seed: u64,
+    /// Most queries running or waiting for a connection at once. When it's
+    /// reached, further queries start late, and their latency includes the wait.
+    pub max_in_flight: usize,
+}
+
+/// Latencies recorded by the queries of an open-loop run, per mix entry.
+struct OpenLoopRecorder {
+    latencies: Vec<Latencies>,
+    /// Queries that finished during the measured time, whenever they were scheduled.
+    completed: Vec<u64>,
+    errors: Vec<u64>,
+}
+
+/// Run the mix against `db` at a fixed rate for the warmup and duration.
+///
+/// Each query is scheduled to start at a time set by `options.arrivals`. Its
+/// latency runs from that time to when its results are back, so it includes
+/// any time spent waiting to be started, for a connection or behind other
+/// queries. Latencies are recorded for the queries scheduled after the warmup,
+/// and throughput counts the queries that finished after it, so a database
+/// that can't keep up shows lower throughput than the target rate. The run
+/// ends once every query scheduled before the end has finished.
+pub async fn run_open_loop(
+    mix: Arc<QueryMix>,
+    db: &PgPool,
+    options: &OpenLoopOptions,
+) -> Result<OpenLoopRun> {
+    if !(options.rate.is_finite() && options.rate > 0.0) {
+        bail!("the rate must be above 0 (got {})", options.rate);
+    }
+    // The end of the run waits for every permit at once, which takes a u32
+    let max_in_flight = match u32::try_from(options.max_in_flight) {
+        Ok(max) if max > 0 => max,
+        _ => bail!(
+            "MAX_IN_FLIGHT must be between 1 and {} (got {})",
+            u32::MAX,
+            options.max_in_flight
+        ),
+    };
+
+    let n = mix.entries.len();
+    let recorder = Arc::new(Mutex::new(OpenLoopRecorder {
+        latencies: vec![Latencies::new(); n],
+        completed: vec![0; n],
+        errors: vec![0; n],
+    }));
+    let in_flight = Arc::new(Semaphore::new(max_in_flight as usize));
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut next = vec![0; n];
+
+    let started_at = SystemTime::now();
+    let start = Instant::now();
+    let measure_from = start + options.warmup;
+    let end = measure_from + options.duration;
+
+    let mut scheduled = start;
+    while scheduled < end {
+        // Sleeping until a time that has passed returns at once, so a dispatcher
+        // that falls behind catches up with a burst rather than skipping queries
+        tokio::time::sleep_until(scheduled.into()).await;
+        let permit = Arc::clone(&in_flight).acquire_owned().await?;
+
+        let i = mix.pick(&mut rng);
+        let query = next[i];
+        next[i] += 1;
+
+        let mix = Arc::clone(&mix);
+        let db = db.clone();
+        let recorder = Arc::clone(&recorder);
+        let decrypt = options.decrypt;
+        tokio::spawn(async move {
+            let outcome = mix.entries[i].run(query, &db, decrypt).await;
+            let finished = Instant::now();
+
+            let mut recorder = recorder.lock().unwrap();
+            match outcome {
+                Ok(_) => {
+                    if scheduled >= measure_from {
+                        recorder.latencies[i].record(finished - scheduled);
+                    }
+                    if finished >= measure_from && finished < end {
+                        recorder.completed[i] += 1;
+                    }
+                }
+                Err(_) if scheduled >= measure_from => recorder.errors[i] += 1,
+                Err(_) => {}
+            }
+            // Only released once recorded, so the totals are complete when every permit is back
+            drop(permit);
+        });
+
+        scheduled += options.arrivals.gap(options.rate, &mut rng);
+    }
+
+    // Every permit is back once the last query has finished
+    let _ = in_flight.acquire_many(max_in_flight).await?;
+
+    let recorder = recorder.lock().unwrap();
+    let seconds = options.duration.as_secs_f64();
+    let mut latencies = Latencies::new();
+    let scenarios = mix
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            latencies.add(&recorder.latencies[i]);
+            OpenLoopScenario {
+                scenario: entry.name.clone(),
+                weight: entry.weight,
+                qps: recorder.completed[i] as f64 / seconds,
+                errors: recorder.errors[i],
+                latency: recorder.latencies[i].summary(),
+                histogram: recorder.latencies[i].clone(),
+            }
+        })
+        .collect();
+
+    Ok(OpenLoopRun {
+        started_at,
+        qps: recorder.completed.iter().sum::<u64>() as f64 / seconds,
+        errors: recorder.errors.iter().sum(),
+        latency: latencies.summary(),
+        histogram: latencies,
+        scenarios,
+    })
+}
+
+/// Throughput and latency of one scenario in an open-loop run.
+#[derive(Debug, Serialize)]
+pub struct OpenLoopScenario {
+    pub scenario: String,
+    pub weight: u32,
+    pub qps: f64,
+    pub errors: u64,
+    /// From each query's scheduled start.
+    pub latency: LatencySummary,
+    #[serde(skip)]
+    pub histogram: Latencies,
+}
+
+/// The results of an open-loop run at one rate.
+#[derive(Debug)]
+pub struct OpenLoopRun {
+    pub started_at: SystemTime,
+    pub qps: f64,
+    pub errors: u64,
+    pub latency: LatencySummary,
+    /// Latencies of every scenario together.
+    pub histogram: Latencies,
+    pub scenarios: Vec<OpenLoopScenario>,
+}
+
+/// An open-loop run with the options it was run with.
+#[derive(Debug, Serialize)]
+pub struct OpenLoopReport {
+    pub workload: String,
+    pub target_rows: String,
+    pub arrivals: String,
+    pub target_qps: f64,
+    pub qps: f64,
+    pub duration_seconds: f64,
+    pub decrypt: bool,
+    pub max_in_flight: usize,
+    pub errors: u64,
+    pub latency: LatencySummary,
+    pub scenarios: Vec<OpenLoopScenario>,
+    #[serde(skip)]
+    started_at: SystemTime,
+    #[serde(skip)]
+    warmup: Duration,
+    #[serde(skip)]
+    histogram: Latencies,
+}
+
+impl OpenLoopReport {
+    pub fn new(
+        workload: impl Into<String>,
+        target_rows: impl Into<String>,
+        options: &OpenLoopOptions,
+        run: OpenLoopRun,
+    ) -> Self {
+        Self {
+            workload: workload.into(),
+            target_rows: target_rows.into(),
+            arrivals: options.arrivals.to_string(),
+            target_qps: options.rate,
+            qps: run.qps,
+            duration_seconds: options.duration.as_secs_f64(),
+            decrypt: options.decrypt,
+            max_in_flight: options.max_in_flight,
+            errors: run.errors,
+            latency: run.latency,
+            scenarios: run.scenarios,
+            started_at: run.started_at,
+            warmup: options.warmup,
+            histogram: run.histogram,
+        }
+    }
+
+    /// Print a summary and write the report to
+    /// `results/load/open_loop{_decrypt}_{workload}_rate_{r}_rows_{n}.json`, with
+    /// the histograms in an HdrHistogram interval log alongside (`.hlog`).
+    ///
+    /// The log has one interval for the whole run with every scenario's
+    /// latencies, followed by one tagged with the name of each scenario.
+    pub fn write(&self) -> Result<PathBuf> {
+        println!(
+            "{} at {} queries/sec ({}): {:.1} queries/sec, p50 {:.2}ms, p99 {:.2}ms, p99.9 {:.2}ms, max {:.2}ms, {} errors",
+            self.workload,
+            self.target_qps,
+            self.arrivals,
+            self.qps,
+            self.latency.p50_ms,
+            self.latency.p99_ms,
+            self.latency.p999_ms,
+            self.latency.max_ms,
+            self.errors,
+        );
+        for s in &self.scenarios {
+            println!(
+                "  {}: {:.1} queries/sec, p50 {:.2}ms, p95 {:.2}ms, p99 {:.2}ms, p99.9 {:.2}ms, {} errors",
+                s.scenario,
+                s.qps,
+                s.latency.p50_ms,
+                s.latency.p95_ms,
+                s.latency.p99_ms,
+                s.latency.p999_ms,
+                s.errors,
+            );
+        }
+
+        let decrypt = if self.decrypt { "_decrypt" } else { "" };
+        let stem = format!(
+            "results/load/open_loop{decrypt}_{}_rate_{}_rows_{}",
+            self.workload, self.target_qps, self.target_rows
+        );
+        fs::create_dir_all("results/load")?;
+
+        let path = PathBuf::from(format!("{stem}.json"));
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+
+        let log_path = format!("{stem}.hlog");
+        fs::write(&log_path, self.histogram_log()?)
+            .with_context(|| format!("failed to write {log_path}"))?;
+
+        Ok(path)
+    }
+
+    fn histogram_log(&self) -> Result<Vec<u8>> {
+        let mut log = Vec::new();
+        let mut serializer = V2DeflateSerializer::new();
+        let mut writer = IntervalLogWriterBuilder::new()
+            .add_comment(&format!(
+                "{} at {} queries/sec ({} arrivals), latency in microseconds from the scheduled start",
+                self.workload, self.target_qps, self.arrivals
+            ))
+            .with_start_time(self.started_at)
+            .with_base_time(self.started_at)
+            .with_max_value_divisor(1000.0)
+            .begin_log_with(&mut log, &mut serializer)?;
+
+        let duration = Duration::from_secs_f64(self.duration_seconds);
+        writer.write_histogram(self.histogram.histogram(), self.warmup, duration, None)?;
+        for s in &self.scenarios {
+            // Tags can't contain commas or whitespace
+            let tag = s.scenario.replace([',', ' '], "_");
+            writer.write_histogram(
+                s.histogram.histogram(),
+                self.warmup,
+                duration,
+                Tag::new(&tag),
+            )?;
+        }
+        drop(writer);
+
+        Ok(log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_weights_reads_name_weight_pairs() {
+        let weights = parse_weights(" ore=2, ore/range_gt_10 = 5,match=0,").unwrap();
+
+        assert_eq!(weights.len(), 3);
+        assert_eq!(weights["ore"], 2);
+        assert_eq!(weights["ore/range_gt_10"], 5);
+        assert_eq!(weights["match"], 0);
+    }
+
+    #[test]
+    fn parse_weights_of_nothing_is_empty() {
+        assert!(parse_weights("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_weights_rejects_a_pair_without_a_weight() {
+        let err = parse_weights("ore=2,match").unwrap_err();
+        assert!(err.to_string().contains("expected name=weight"), "{err}");
+    }
+
+    #[test]
+    fn parse_weights_rejects_negative_and_non_integer_weights() {
+        for weights in ["ore=-1", "ore=1.5", "ore=", "ore=many"] {
+            let err = parse_weights(weights).unwrap_err();
+            assert!(
+                err.to_string().contains("expected an integer"),
+                "{weights}: {err}"
+            );
+        }
+    }
+}